@@ -0,0 +1,31 @@
+//! Writes a program that follows the grammar from the fuzzer's bytes and runs it. Every generated
+//! program has to parse, so this also finds places where the parser and `grammar.txt` disagree.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use happy::{
+    Generator,
+    Interpreter,
+    Limits,
+};
+
+
+fuzz_target!(|data:&[u8]|{
+    let source=Generator::new(data).program();
+    let program=match happy::parse(&source,"generated.happy") {
+        Ok(program)=>program,
+        Err(errors)=>panic!("a generated program did not parse: {}\n{}",errors[0].kind,source),
+    };
+    if let Some(error)=program.link().first() {
+        panic!("a generated program has a call that goes nowhere: {}\n{}",error.kind,source);
+    }
+    let mut interpreter=Interpreter::new();
+    interpreter.set_limits(Limits {
+        operations:Some(100_000),
+        call_depth:Some(100),
+        memory:Some(1<<20),
+    });
+    interpreter.set_stdin(&b"1\nsome line\n2.5\n"[..]);
+    interpreter.set_stdout(std::io::sink());
+    interpreter.set_stderr(std::io::sink());
+    let _=program.run_with(&mut interpreter);
+});
@@ -0,0 +1,10 @@
+//! Parses arbitrary text. Finds panics and parser loops that never finish.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+
+fuzz_target!(|data:&[u8]|{
+    if let Ok(source)=std::str::from_utf8(data) {
+        let _=happy::parse(source,"fuzz.happy");
+    }
+});
@@ -0,0 +1,30 @@
+//! Parses arbitrary text and runs it if it parsed. Limits stop programs that loop or recurse
+//! forever, so a hang means the interpreter itself is stuck.
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+use happy::{
+    Interpreter,
+    Limits,
+};
+
+
+fuzz_target!(|data:&[u8]|{
+    let source=match std::str::from_utf8(data) {
+        Ok(source)=>source,
+        Err(_)=>return,
+    };
+    let program=match happy::parse(source,"fuzz.happy") {
+        Ok(program)=>program,
+        Err(_)=>return,
+    };
+    let mut interpreter=Interpreter::new();
+    interpreter.set_limits(Limits {
+        operations:Some(100_000),
+        call_depth:Some(100),
+        memory:Some(1<<20),
+    });
+    interpreter.set_stdin(&b"1\nsome line\n2.5\n"[..]);
+    interpreter.set_stdout(std::io::sink());
+    interpreter.set_stderr(std::io::sink());
+    let _=program.run_with(&mut interpreter);
+});
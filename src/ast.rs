@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use crate::{
+    data::Data,
+    span::Span,
+};
+
+
+#[derive(Debug)]
+pub struct Operation<'doc> {
+    pub kind:OperationKind<'doc>,
+    pub span:Span<'doc>,
+    /// The source of the operation, including any blocks it has.
+    pub text:&'doc str,
+}
+#[derive(Debug)]
+pub enum OperationKind<'doc> {
+    Add([&'doc str;2]),
+    Sub([&'doc str;2]),
+    Mul([&'doc str;2]),
+    Div([&'doc str;2]),
+    Mod([&'doc str;2]),
+    Assign(&'doc str,Value<'doc>),
+    Equal([&'doc str;2]),
+    NotEqual([&'doc str;2]),
+    Greater([&'doc str;2]),
+    Less([&'doc str;2]),
+    GreaterEqual([&'doc str;2]),
+    LessEqual([&'doc str;2]),
+    And([&'doc str;2]),
+    Or([&'doc str;2]),
+    Not(&'doc str),
+    Print(&'doc str),
+    Input(&'doc str,InputKind),
+    Get(&'doc str,&'doc str,Value<'doc>),
+    Set(&'doc str,Value<'doc>,Value<'doc>),
+    Push(&'doc str,Value<'doc>),
+    Pop(&'doc str,&'doc str),
+    Length(&'doc str,&'doc str),
+    Remove(&'doc str,Value<'doc>),
+    Contains(&'doc str,&'doc str,Value<'doc>),
+    Keys(&'doc str,&'doc str),
+    Call(FunctionCall<'doc>),
+    AssignCall(&'doc str,FunctionCall<'doc>),
+    Return(Value<'doc>),
+    Conditional {
+        to_compare:Box<Operation<'doc>>,
+        inner:Vec<Operation<'doc>>,
+        otherwise:Option<Vec<Operation<'doc>>>,
+    },
+    Loop {
+        condition:Box<Operation<'doc>>,
+        body:Vec<Operation<'doc>>,
+    },
+    Break,
+    Continue,
+}
+impl<'doc> OperationKind<'doc> {
+    /// The name of the operation, like `Add`.
+    pub fn name(&self)->&'static str {
+        use OperationKind::*;
+        match self {
+            Add(_)=>"Add",
+            Sub(_)=>"Sub",
+            Mul(_)=>"Mul",
+            Div(_)=>"Div",
+            Mod(_)=>"Mod",
+            Assign(..)=>"Assign",
+            Equal(_)=>"Equal",
+            NotEqual(_)=>"NotEqual",
+            Greater(_)=>"Greater",
+            Less(_)=>"Less",
+            GreaterEqual(_)=>"GreaterEqual",
+            LessEqual(_)=>"LessEqual",
+            And(_)=>"And",
+            Or(_)=>"Or",
+            Not(_)=>"Not",
+            Print(_)=>"Print",
+            Input(..)=>"Input",
+            Get(..)=>"Get",
+            Set(..)=>"Set",
+            Push(..)=>"Push",
+            Pop(..)=>"Pop",
+            Length(..)=>"Length",
+            Remove(..)=>"Remove",
+            Contains(..)=>"Contains",
+            Keys(..)=>"Keys",
+            Call(_)=>"Call",
+            AssignCall(..)=>"AssignCall",
+            Return(_)=>"Return",
+            Conditional{..}=>"Conditional",
+            Loop{..}=>"Loop",
+            Break=>"Break",
+            Continue=>"Continue",
+        }
+    }
+    /// The variables the operation itself reads or changes, without the operations nested in it.
+    pub fn variables(&self)->Vec<&'doc str> {
+        use OperationKind::*;
+        let mut variables=Vec::new();
+        match self {
+            Add(vars)|Sub(vars)|Mul(vars)|Div(vars)|Mod(vars)|Equal(vars)|NotEqual(vars)|Greater(vars)|
+            Less(vars)|GreaterEqual(vars)|LessEqual(vars)|And(vars)|Or(vars)=>variables.extend(vars),
+            Not(var)|Print(var)|Input(var,_)=>variables.push(*var),
+            Assign(var,value)|Push(var,value)|Remove(var,value)=>{
+                variables.push(*var);
+                value.data.variables(&mut variables);
+            },
+            Get(var,list,index)|Contains(var,list,index)=>{
+                variables.extend([*var,*list]);
+                index.data.variables(&mut variables);
+            },
+            Set(list,index,value)=>{
+                variables.push(*list);
+                index.data.variables(&mut variables);
+                value.data.variables(&mut variables);
+            },
+            Pop(list,var)=>variables.extend([*list,*var]),
+            Length(var,list)|Keys(var,list)=>variables.extend([*var,*list]),
+            Call(call)=>call.arguments.iter().for_each(|argument|argument.data.variables(&mut variables)),
+            AssignCall(var,call)=>{
+                variables.push(*var);
+                call.arguments.iter().for_each(|argument|argument.data.variables(&mut variables));
+            },
+            Return(value)=>value.data.variables(&mut variables),
+            Conditional{..}|Loop{..}|Break|Continue=>{},
+        }
+        let mut unique=Vec::new();
+        for var in variables {
+            if !unique.contains(&var) {
+                unique.push(var);
+            }
+        }
+        return unique;
+    }
+}
+/// What an input operation like `a$` reads from stdin.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum InputKind {
+    /// `a$`: the next line without its line ending, or `None` at the end of the input.
+    Line,
+    /// `a$#`: the next line as a number, or `None` at the end of the input.
+    Number,
+    /// `a$$`: everything left in the input.
+    All,
+    /// `a$?`: whether the end of the input was reached.
+    Eof,
+}
+/// A value as it is written in the source, like `1`, `"a"`, `[x,2]` or `x`.
+#[derive(Debug)]
+pub struct Value<'doc> {
+    pub data:Data<'doc>,
+    /// Where the value starts. Values that aren't written out, like the one of a bare `^`, are
+    /// where they would be.
+    pub span:Span<'doc>,
+}
+#[derive(Debug)]
+pub struct FunctionCall<'doc> {
+    pub class:u32,
+    pub function:&'doc str,
+    pub arguments:Vec<Value<'doc>>,
+    /// Where the number of the class being called is.
+    pub span:Span<'doc>,
+    /// The source of the call, like `0>A(x,1)`.
+    pub text:&'doc str,
+}
+#[derive(Debug)]
+pub struct Class<'doc> {
+    pub functions:HashMap<&'doc str,Function<'doc>>,
+    /// Where the number of the class is.
+    pub span:Span<'doc>,
+}
+#[derive(Debug)]
+pub struct Function<'doc> {
+    pub parameters:Vec<&'doc str>,
+    pub operations:Vec<Operation<'doc>>,
+    /// Where the name of the function is.
+    pub span:Span<'doc>,
+    /// The source of the whole function, from its name to the closing `;`.
+    pub text:&'doc str,
+}
+#[derive(Debug)]
+pub struct Program<'doc> {
+    pub classes:HashMap<u32,Class<'doc>>,
+    pub statements:Vec<FunctionCall<'doc>>,
+}
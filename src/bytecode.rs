@@ -0,0 +1,121 @@
+use crate::{
+    ast::InputKind,
+    data::{
+        Data,
+        Key,
+    },
+    error::RuntimeErrorKind,
+    span::Span,
+};
+
+
+/// An operation on a variable that takes its other operand from the stack, like `a+b`.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Mod,
+    Equal,
+    NotEqual,
+    Greater,
+    Less,
+    GreaterEqual,
+    LessEqual,
+    And,
+    Or,
+}
+impl BinaryOp {
+    pub(crate) fn apply<'doc>(self,data:&mut Data<'doc>,other:Data<'doc>)->Result<(),RuntimeErrorKind> {
+        use BinaryOp::*;
+        match self {
+            Add=>data.arithmetic("+",other),
+            Sub=>data.arithmetic("-",other),
+            Mul=>data.arithmetic("*",other),
+            Div=>data.arithmetic("/",other),
+            Mod=>data.arithmetic("//",other),
+            Equal=>data.compare("==",other),
+            NotEqual=>data.compare("!=",other),
+            Greater=>data.compare(">",other),
+            Less=>data.compare("<",other),
+            GreaterEqual=>data.compare(">=",other),
+            LessEqual=>data.compare("<=",other),
+            And=>data.and(other),
+            Or=>data.or(other),
+        }
+    }
+}
+
+
+/// A single VM instruction. `usize` operands are variable slots unless noted otherwise.
+#[derive(Clone,Debug,PartialEq)]
+pub enum Instruction<'doc> {
+    /// Push a constant.
+    Const(Data<'doc>),
+    /// Push a copy of a variable.
+    Load(usize),
+    /// Pop a value into a variable.
+    Store(usize),
+    /// Pop a value and drop it.
+    Discard,
+    /// Pop the right operand and apply the operation to the variable in place.
+    Binary(usize,BinaryOp),
+    Not(usize),
+    Print(usize),
+    Input(usize,InputKind),
+    /// Pop this many values and push them as a list.
+    List(usize),
+    /// Pop a value for each key and push them as a map.
+    Map(Vec<Key>),
+    /// Pop an index and push that item of the variable.
+    Get(usize),
+    /// Pop a value and an index and set that item of the variable.
+    Set(usize),
+    /// Pop a value and append it to the variable.
+    Append(usize),
+    /// Push the last item of the variable after removing it.
+    Pop(usize),
+    /// Pop an index and remove that item from the variable.
+    Remove(usize),
+    /// Pop a key and push whether the variable contains it.
+    Contains(usize),
+    Keys(usize),
+    Length(usize),
+    /// Call the function with this index, popping its arguments and pushing its return value.
+    Call(usize),
+    /// Pop the return value and leave the function.
+    Return,
+    /// Continue at this instruction index.
+    Jump(usize),
+    /// Pop a value and continue at this instruction index unless it is `true`.
+    JumpUnlessTrue(usize),
+    /// Stop with an error that was already known when compiling, like a call to an unknown function.
+    Fail(RuntimeErrorKind),
+}
+
+
+#[derive(Debug)]
+pub struct CompiledFunction<'doc> {
+    pub class:u32,
+    pub name:&'doc str,
+    /// The first `parameters` slots hold the arguments.
+    pub parameters:usize,
+    /// The variable name of every slot.
+    pub slots:Vec<&'doc str>,
+    pub code:Vec<Instruction<'doc>>,
+    /// The source of every instruction, `None` for the ones no operation or call stands for.
+    pub spans:Vec<Option<Span<'doc>>>,
+    /// The operations that start at every instruction, outermost first, so the VM counts them
+    /// like the interpreter does for [`Limits::operations`](crate::Limits::operations).
+    pub operations:Vec<Vec<Span<'doc>>>,
+}
+
+
+/// A [`Program`](crate::Program) lowered by [`Program::compile`](crate::Program::compile).
+#[derive(Debug)]
+pub struct Bytecode<'doc> {
+    pub functions:Vec<CompiledFunction<'doc>>,
+    /// The index of the function holding the top-level statements.
+    pub main:usize,
+}
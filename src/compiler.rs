@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+use crate::{
+    ast::*,
+    bytecode::*,
+    data::Data,
+    error::RuntimeErrorKind,
+    span::Span,
+    suggest::similar_function,
+};
+
+
+/// The index and parameter count of every function.
+type Targets<'doc>=HashMap<(u32,&'doc str),(usize,usize)>;
+
+
+struct LoopLabels {
+    start:usize,
+    breaks:Vec<usize>,
+}
+
+
+/// Compiles the operations of a single function.
+struct FunctionCompiler<'a,'doc> {
+    targets:&'a Targets<'doc>,
+    slots:Vec<&'doc str>,
+    slot_names:HashMap<&'doc str,usize>,
+    code:Vec<Instruction<'doc>>,
+    spans:Vec<Option<Span<'doc>>>,
+    operations:Vec<Vec<Span<'doc>>>,
+    /// Operations that start at the next instruction.
+    starting:Vec<Span<'doc>>,
+    loops:Vec<LoopLabels>,
+}
+impl<'a,'doc> FunctionCompiler<'a,'doc> {
+    fn new(targets:&'a Targets<'doc>,parameters:&[&'doc str])->Self {
+        let mut compiler=FunctionCompiler {
+            targets,
+            slots:Vec::new(),
+            slot_names:HashMap::new(),
+            code:Vec::new(),
+            spans:Vec::new(),
+            operations:Vec::new(),
+            starting:Vec::new(),
+            loops:Vec::new(),
+        };
+        for parameter in parameters {
+            compiler.slot(parameter);
+        }
+        return compiler;
+    }
+    fn finish(mut self,class:u32,name:&'doc str,parameters:usize)->CompiledFunction<'doc> {
+        self.emit(Instruction::Const(Data::None),None);
+        self.emit(Instruction::Return,None);
+        return CompiledFunction {
+            class,
+            name,
+            parameters,
+            slots:self.slots,
+            code:self.code,
+            spans:self.spans,
+            operations:self.operations,
+        };
+    }
+    fn slot(&mut self,name:&'doc str)->usize {
+        if let Some(slot)=self.slot_names.get(name) {
+            return *slot;
+        }
+        self.slots.push(name);
+        self.slot_names.insert(name,self.slots.len()-1);
+        return self.slots.len()-1;
+    }
+    fn emit(&mut self,instruction:Instruction<'doc>,span:Option<Span<'doc>>)->usize {
+        self.code.push(instruction);
+        self.spans.push(span);
+        self.operations.push(std::mem::take(&mut self.starting));
+        return self.code.len()-1;
+    }
+    /// Point the jump at `at` to the next instruction.
+    fn patch(&mut self,at:usize) {
+        let target=self.code.len();
+        match &mut self.code[at] {
+            Instruction::Jump(to)|Instruction::JumpUnlessTrue(to)=>*to=target,
+            _=>unreachable!("only jumps can be patched"),
+        }
+    }
+    /// Push the value of `data`, looking up the variables in it.
+    fn data(&mut self,data:&Data<'doc>,span:Option<Span<'doc>>) {
+        if is_constant(data) {
+            self.emit(Instruction::Const(data.clone()),span);
+            return;
+        }
+        match data {
+            Data::Var(name)=>{
+                let slot=self.slot(name);
+                self.emit(Instruction::Load(slot),span);
+            },
+            Data::List(items)=>{
+                for item in items {
+                    self.data(item,span);
+                }
+                self.emit(Instruction::List(items.len()),span);
+            },
+            Data::Map(entries)=>{
+                for value in entries.values() {
+                    self.data(value,span);
+                }
+                self.emit(Instruction::Map(entries.keys().cloned().collect()),span);
+            },
+            _=>unreachable!("everything else is a constant"),
+        }
+    }
+    fn call(&mut self,call:&FunctionCall<'doc>,span:Option<Span<'doc>>,classes:&HashMap<u32,Class<'doc>>) {
+        let FunctionCall{class,function,arguments,..}=call;
+        match self.targets.get(&(*class,*function)) {
+            Some((index,parameters)) if *parameters==arguments.len()=>{
+                for argument in arguments {
+                    self.data(&argument.data,span);
+                }
+                self.emit(Instruction::Call(*index),span);
+            },
+            Some((_,parameters))=>{
+                let kind=RuntimeErrorKind::ArgumentCount {
+                    class:*class,
+                    function:function.to_string(),
+                    expected:*parameters,
+                    found:arguments.len(),
+                };
+                self.emit(Instruction::Fail(kind),span);
+            },
+            None if classes.contains_key(class)=>{
+                let kind=RuntimeErrorKind::UnknownFunction {
+                    class:*class,
+                    function:function.to_string(),
+                    suggestion:similar_function(classes,*class,function),
+                };
+                self.emit(Instruction::Fail(kind),span);
+            },
+            None=>{
+                self.emit(Instruction::Fail(RuntimeErrorKind::UnknownClass(*class)),span);
+            },
+        }
+    }
+    fn block(&mut self,operations:&[Operation<'doc>],classes:&HashMap<u32,Class<'doc>>) {
+        for op in operations {
+            self.operation(op,false,classes);
+        }
+    }
+    /// Compile `operation`. With `value` its result is left on the stack, like a conditional needs.
+    fn operation(&mut self,operation:&Operation<'doc>,value:bool,classes:&HashMap<u32,Class<'doc>>) {
+        let span=Some(operation.span);
+        self.starting.push(operation.span);
+        use OperationKind::*;
+        let result=match &operation.kind {
+            Add(vars)=>self.binary(vars,BinaryOp::Add,span),
+            Sub(vars)=>self.binary(vars,BinaryOp::Sub,span),
+            Mul(vars)=>self.binary(vars,BinaryOp::Mul,span),
+            Div(vars)=>self.binary(vars,BinaryOp::Div,span),
+            Mod(vars)=>self.binary(vars,BinaryOp::Mod,span),
+            Equal(vars)=>self.binary(vars,BinaryOp::Equal,span),
+            NotEqual(vars)=>self.binary(vars,BinaryOp::NotEqual,span),
+            Greater(vars)=>self.binary(vars,BinaryOp::Greater,span),
+            Less(vars)=>self.binary(vars,BinaryOp::Less,span),
+            GreaterEqual(vars)=>self.binary(vars,BinaryOp::GreaterEqual,span),
+            LessEqual(vars)=>self.binary(vars,BinaryOp::LessEqual,span),
+            And(vars)=>self.binary(vars,BinaryOp::And,span),
+            Or(vars)=>self.binary(vars,BinaryOp::Or,span),
+            Not(var)=>{
+                let slot=self.slot(var);
+                self.emit(Instruction::Not(slot),span);
+                Some(slot)
+            },
+            Assign(var,value)=>{
+                self.data(&value.data,span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Print(var)=>{
+                let slot=self.slot(var);
+                self.emit(Instruction::Print(slot),span);
+                None
+            },
+            Input(var,kind)=>{
+                let slot=self.slot(var);
+                self.emit(Instruction::Input(slot,*kind),span);
+                Some(slot)
+            },
+            Get(var,list,index)=>{
+                self.data(&index.data,span);
+                let list=self.slot(list);
+                self.emit(Instruction::Get(list),span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Set(list,index,value)=>{
+                self.data(&index.data,span);
+                self.data(&value.data,span);
+                let slot=self.slot(list);
+                self.emit(Instruction::Set(slot),span);
+                Some(slot)
+            },
+            Push(list,value)=>{
+                self.data(&value.data,span);
+                let slot=self.slot(list);
+                self.emit(Instruction::Append(slot),span);
+                Some(slot)
+            },
+            Pop(list,var)=>{
+                let list=self.slot(list);
+                self.emit(Instruction::Pop(list),span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Length(var,list)=>{
+                let list=self.slot(list);
+                self.emit(Instruction::Length(list),span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Remove(list,index)=>{
+                self.data(&index.data,span);
+                let slot=self.slot(list);
+                self.emit(Instruction::Remove(slot),span);
+                Some(slot)
+            },
+            Contains(var,map,key)=>{
+                self.data(&key.data,span);
+                let map=self.slot(map);
+                self.emit(Instruction::Contains(map),span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Keys(var,map)=>{
+                let map=self.slot(map);
+                self.emit(Instruction::Keys(map),span);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Call(call)=>{
+                self.call(call,span,classes);
+                self.emit(Instruction::Discard,span);
+                None
+            },
+            AssignCall(var,call)=>{
+                self.call(call,span,classes);
+                let slot=self.slot(var);
+                self.emit(Instruction::Store(slot),span);
+                Some(slot)
+            },
+            Return(value)=>{
+                self.data(&value.data,span);
+                self.emit(Instruction::Return,span);
+                None
+            },
+            Conditional{to_compare,inner,otherwise}=>{
+                self.operation(to_compare,true,classes);
+                let skip_inner=self.emit(Instruction::JumpUnlessTrue(0),span);
+                self.block(inner,classes);
+                match otherwise {
+                    Some(otherwise)=>{
+                        let skip_otherwise=self.emit(Instruction::Jump(0),span);
+                        self.patch(skip_inner);
+                        self.block(otherwise,classes);
+                        self.patch(skip_otherwise);
+                    },
+                    None=>self.patch(skip_inner),
+                }
+                None
+            },
+            Loop{condition,body}=>{
+                // The loop itself only counts once, so it starts before the condition that every
+                // round jumps back to.
+                let next=self.code.len()+1;
+                self.emit(Instruction::Jump(next),span);
+                let start=self.code.len();
+                self.operation(condition,true,classes);
+                let exit=self.emit(Instruction::JumpUnlessTrue(0),span);
+                self.loops.push(LoopLabels{start,breaks:Vec::new()});
+                self.block(body,classes);
+                self.emit(Instruction::Jump(start),span);
+                self.patch(exit);
+                for at in self.loops.pop().unwrap().breaks {
+                    self.patch(at);
+                }
+                None
+            },
+            Break=>{
+                match self.loops.len() {
+                    0=>{self.emit(Instruction::Fail(RuntimeErrorKind::OutsideLoop("@>")),span);},
+                    _=>{
+                        let at=self.emit(Instruction::Jump(0),span);
+                        self.loops.last_mut().unwrap().breaks.push(at);
+                    },
+                }
+                None
+            },
+            Continue=>{
+                match self.loops.last() {
+                    Some(labels)=>{
+                        let start=labels.start;
+                        self.emit(Instruction::Jump(start),span);
+                    },
+                    None=>{self.emit(Instruction::Fail(RuntimeErrorKind::OutsideLoop("@<")),span);},
+                }
+                None
+            },
+        };
+        if value {
+            match result {
+                Some(slot)=>self.emit(Instruction::Load(slot),span),
+                None=>self.emit(Instruction::Const(Data::None),span),
+            };
+        }
+    }
+    fn binary(&mut self,vars:&[&'doc str;2],op:BinaryOp,span:Option<Span<'doc>>)->Option<usize> {
+        let other=self.slot(vars[1]);
+        self.emit(Instruction::Load(other),span);
+        let slot=self.slot(vars[0]);
+        self.emit(Instruction::Binary(slot,op),span);
+        return Some(slot);
+    }
+}
+
+
+/// Whether `data` contains no variables.
+fn is_constant(data:&Data)->bool {
+    match data {
+        Data::Var(_)=>false,
+        Data::List(items)=>items.iter().all(is_constant),
+        Data::Map(entries)=>entries.values().all(is_constant),
+        _=>true,
+    }
+}
+
+
+impl<'doc> Program<'doc> {
+    /// Lower the program to bytecode with resolved variable slots and call targets.
+    pub fn compile(&self)->Bytecode<'doc> {
+        let mut names=Vec::new();
+        for (class,c) in self.classes.iter() {
+            for (name,function) in c.functions.iter() {
+                names.push((*class,*name,function));
+            }
+        }
+        names.sort_by_key(|(class,name,_)|(*class,*name));
+        let targets:Targets=names.iter()
+            .enumerate()
+            .map(|(i,(class,name,function))|((*class,*name),(i,function.parameters.len())))
+            .collect();
+        let mut functions=Vec::new();
+        for (class,name,function) in names {
+            let mut compiler=FunctionCompiler::new(&targets,&function.parameters);
+            compiler.block(&function.operations,&self.classes);
+            functions.push(compiler.finish(class,name,function.parameters.len()));
+        }
+        let mut compiler=FunctionCompiler::new(&targets,&[]);
+        for statement in self.statements.iter() {
+            compiler.call(statement,Some(statement.span),&self.classes);
+            compiler.emit(Instruction::Discard,None);
+        }
+        functions.push(compiler.finish(0,"",0));
+        return Bytecode {
+            main:functions.len()-1,
+            functions,
+        };
+    }
+}
@@ -0,0 +1,466 @@
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult,
+    },
+    cmp::Ordering,
+    collections::BTreeMap,
+    mem::size_of,
+};
+use crate::error::RuntimeErrorKind;
+
+
+#[derive(Clone,Debug)]
+pub enum Data<'doc> {
+    Var(&'doc str),
+    Int(i64),
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    List(Vec<Data<'doc>>),
+    Map(BTreeMap<Key,Data<'doc>>),
+    None,
+}
+impl<'doc> PartialEq for Data<'doc> {
+    fn eq(&self,other:&Self)->bool {
+        use Data::*;
+        match (self,other) {
+            (Bool(b1),Bool(b2))=>b1==b2,
+            (Str(s1),Str(s2))=>s1==s2,
+            (Number(n1),Number(n2))=>n1==n2,
+            (Int(n1),Int(n2))=>n1==n2,
+            (Int(n1),Number(n2))=>compare_int(*n1,*n2)==Some(Ordering::Equal),
+            (Number(n1),Int(n2))=>compare_int(*n2,*n1)==Some(Ordering::Equal),
+            (List(l1),List(l2))=>l1==l2,
+            (Map(m1),Map(m2))=>m1==m2,
+            (None,None)=>true,
+            _=>false,
+        }
+    }
+}
+impl<'doc> PartialOrd for Data<'doc> {
+    fn partial_cmp(&self,other:&Self)->Option<Ordering> {
+        use Data::*;
+        match (self,other) {
+            (Bool(b1),Bool(b2))=>b1.partial_cmp(b2),
+            (Str(s1),Str(s2))=>s1.partial_cmp(s2),
+            (Number(n1),Number(n2))=>n1.partial_cmp(n2),
+            (Int(n1),Int(n2))=>n1.partial_cmp(n2),
+            (Int(n1),Number(n2))=>compare_int(*n1,*n2),
+            (Number(n1),Int(n2))=>compare_int(*n2,*n1).map(Ordering::reverse),
+            (List(l1),List(l2))=>l1.partial_cmp(l2),
+            (Map(m1),Map(m2))=>m1.partial_cmp(m2),
+            (None,None)=>Some(Ordering::Equal),
+            _=>Option::None,
+        }
+    }
+}
+impl<'doc> Display for Data<'doc> {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        use Data::*;
+        match self {
+            Bool(b)=>write!(f,"{}",b),
+            Var(v)=>write!(f,"Var `{}`",v),
+            Int(n)=>write!(f,"{}",n),
+            Number(n)=>write!(f,"{}",n),
+            Str(s)=>write!(f,"{}",s),
+            List(items)=>{
+                write!(f,"[")?;
+                for (i,item) in items.iter().enumerate() {
+                    if i>0 {
+                        write!(f,", ")?;
+                    }
+                    match item {
+                        Str(s)=>write!(f,"{:?}",s)?,
+                        item=>write!(f,"{}",item)?,
+                    }
+                }
+                write!(f,"]")
+            },
+            Map(entries)=>{
+                write!(f,"{{")?;
+                for (i,(key,value)) in entries.iter().enumerate() {
+                    if i>0 {
+                        write!(f,", ")?;
+                    }
+                    match value {
+                        Str(s)=>write!(f,"{}: {:?}",key,s)?,
+                        value=>write!(f,"{}: {}",key,value)?,
+                    }
+                }
+                write!(f,"}}")
+            },
+            None=>write!(f,"None"),
+        }
+    }
+}
+impl<'doc> Default for Data<'doc> {
+    fn default()->Self {Data::None}
+}
+impl<'doc> Data<'doc> {
+    pub fn var(&self)->Option<&'doc str> {
+        match self {
+            Self::Var(s)=>Some(s),
+            _=>None,
+        }
+    }
+    /// Add every variable in this data, including the items of lists and maps, to `variables`.
+    pub fn variables(&self,variables:&mut Vec<&'doc str>) {
+        match self {
+            Data::Var(name)=>variables.push(name),
+            Data::List(items)=>items.iter().for_each(|item|item.variables(variables)),
+            Data::Map(entries)=>entries.values().for_each(|value|value.variables(variables)),
+            _=>{},
+        }
+    }
+    /// Like `to_string`, but strings are quoted so they can be told apart from other values.
+    pub fn quoted(&self)->String {
+        match self {
+            Data::Str(s)=>format!("{:?}",s),
+            data=>data.to_string(),
+        }
+    }
+    /// The value of an int or number as a float.
+    pub fn as_f64(&self)->Option<f64> {
+        match self {
+            Self::Int(n)=>Some(*n as f64),
+            Self::Number(n)=>Some(*n),
+            _=>None,
+        }
+    }
+    pub fn type_name(&self)->&'static str {
+        use Data::*;
+        match self {
+            Var(_)=>"variable",
+            Int(_)=>"int",
+            Number(_)=>"number",
+            Str(_)=>"string",
+            Bool(_)=>"bool",
+            List(_)=>"list",
+            Map(_)=>"map",
+            None=>"none",
+        }
+    }
+    fn mismatch(&self,operation:&'static str,other:&Self)->RuntimeErrorKind {
+        RuntimeErrorKind::TypeMismatch {
+            operation,
+            left:self.type_name(),
+            right:other.type_name(),
+        }
+    }
+    /// Replace `self` with the result of applying `operation` to it and `other`. Ints stay ints
+    /// unless a number is involved, and fail instead of wrapping when they overflow.
+    pub(crate) fn arithmetic(&mut self,operation:&'static str,other:Self)->Result<(),RuntimeErrorKind> {
+        use Data::*;
+        match (&mut *self,other) {
+            (Int(n1),Int(n2))=>*n1=int_arithmetic(operation,*n1,n2)?,
+            (Number(n1),Number(n2))=>*n1=float_arithmetic(operation,*n1,n2)?,
+            (Number(n1),Int(n2))=>*n1=float_arithmetic(operation,*n1,n2 as f64)?,
+            (data@Int(_),Number(n2))=>{
+                let n1=data.as_f64().unwrap();
+                *data=Number(float_arithmetic(operation,n1,n2)?);
+            },
+            (Str(s1),Str(s2)) if operation=="+"=>*s1+=&s2,
+            (List(l1),List(l2)) if operation=="+"=>l1.extend(l2),
+            (Map(m1),Map(m2)) if operation=="+"=>m1.extend(m2),
+            (data,other)=>return Err(data.mismatch(operation,&other)),
+        }
+        return Ok(());
+    }
+    /// Replace `self` with the result of comparing it to `other`.
+    pub(crate) fn compare(&mut self,operation:&'static str,other:Self)->Result<(),RuntimeErrorKind> {
+        let result=match operation {
+            "=="=>*self==other,
+            "!="=>*self!=other,
+            _ if self.as_f64().is_some()&&other.as_f64().is_some()=>match operation {
+                ">"=>*self>other,
+                "<"=>*self<other,
+                ">="=>*self>=other,
+                _=>*self<=other,
+            },
+            _ if self.type_name()!=other.type_name()=>return Err(self.mismatch(operation,&other)),
+            ">"=>*self>other,
+            "<"=>*self<other,
+            ">="=>*self>=other,
+            "<="=>*self<=other,
+            _=>unreachable!("unknown comparison `{}`",operation),
+        };
+        *self=Data::Bool(result);
+        return Ok(());
+    }
+    pub(crate) fn and(&mut self,other:Self)->Result<(),RuntimeErrorKind> {
+        use Data::*;
+        match (self,other) {
+            (Bool(b1),Bool(b2))=>*b1=*b1&&b2,
+            (data,other)=>return Err(data.mismatch("&",&other)),
+        }
+        return Ok(());
+    }
+    pub(crate) fn or(&mut self,other:Self)->Result<(),RuntimeErrorKind> {
+        use Data::*;
+        match (self,other) {
+            (Bool(b1),Bool(b2))=>*b1=*b1||b2,
+            (data,other)=>return Err(data.mismatch("|",&other)),
+        }
+        return Ok(());
+    }
+    pub(crate) fn not(&mut self)->Result<(),RuntimeErrorKind> {
+        use Data::*;
+        match self {
+            Bool(b)=>*b=!*b,
+            data=>return Err(RuntimeErrorKind::InvalidOperand{operation:"!",found:data.type_name()}),
+        }
+        return Ok(());
+    }
+    /// Turn `index` into a position in this list, which can be one past the end.
+    fn position(&self,items:&[Self])->Result<usize,RuntimeErrorKind> {
+        match self {
+            Data::Int(n) if *n>=0=>{
+                let index=*n as usize;
+                if index>items.len() {
+                    return Err(RuntimeErrorKind::IndexOutOfRange{index,length:items.len()});
+                }
+                return Ok(index);
+            },
+            index=>Err(RuntimeErrorKind::InvalidIndex(index.to_string())),
+        }
+    }
+    pub(crate) fn get(&self,index:&Self)->Result<Self,RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>{
+                let i=index.position(items)?;
+                return items.get(i).cloned().ok_or(RuntimeErrorKind::IndexOutOfRange{index:i,length:items.len()});
+            },
+            Data::Map(entries)=>{
+                let key=index.key()?;
+                return entries.get(&key).cloned().ok_or(RuntimeErrorKind::MissingKey(key.to_string()));
+            },
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:"[]",found:data.type_name()}),
+        }
+    }
+    pub(crate) fn set(&mut self,index:&Self,value:Self)->Result<(),RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>{
+                let i=index.position(items)?;
+                match items.get_mut(i) {
+                    Some(item)=>*item=value,
+                    Option::None=>return Err(RuntimeErrorKind::IndexOutOfRange{index:i,length:items.len()}),
+                }
+                return Ok(());
+            },
+            Data::Map(entries)=>{
+                entries.insert(index.key()?,value);
+                return Ok(());
+            },
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:"[]",found:data.type_name()}),
+        }
+    }
+    pub(crate) fn push(&mut self,value:Self)->Result<(),RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>items.push(value),
+            data=>return Err(RuntimeErrorKind::InvalidOperand{operation:"<<",found:data.type_name()}),
+        }
+        return Ok(());
+    }
+    pub(crate) fn pop(&mut self)->Result<Self,RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>items.pop().ok_or(RuntimeErrorKind::EmptyList),
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:">>",found:data.type_name()}),
+        }
+    }
+    /// Remove an item from a list or an entry from a map.
+    pub(crate) fn remove(&mut self,index:&Self)->Result<(),RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>{
+                let i=index.position(items)?;
+                if i>=items.len() {
+                    return Err(RuntimeErrorKind::IndexOutOfRange{index:i,length:items.len()});
+                }
+                items.remove(i);
+            },
+            Data::Map(entries)=>{
+                let key=index.key()?;
+                if entries.remove(&key).is_none() {
+                    return Err(RuntimeErrorKind::MissingKey(key.to_string()));
+                }
+            },
+            data=>return Err(RuntimeErrorKind::InvalidOperand{operation:"~",found:data.type_name()}),
+        }
+        return Ok(());
+    }
+    pub(crate) fn contains(&self,key:&Self)->Result<bool,RuntimeErrorKind> {
+        match self {
+            Data::Map(entries)=>Ok(entries.contains_key(&key.key()?)),
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:"[]?",found:data.type_name()}),
+        }
+    }
+    /// The keys of a map in order.
+    pub(crate) fn keys(&self)->Result<Self,RuntimeErrorKind> {
+        match self {
+            Data::Map(entries)=>Ok(Data::List(entries.keys().map(Key::to_data).collect())),
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:"[]",found:data.type_name()}),
+        }
+    }
+    fn key(&self)->Result<Key,RuntimeErrorKind> {
+        return Key::new(self).ok_or_else(||RuntimeErrorKind::InvalidKey(self.type_name()));
+    }
+    /// Roughly how many bytes of heap the value uses for strings, list items and map entries.
+    pub fn size(&self)->usize {
+        match self {
+            Data::Str(s)=>s.len(),
+            Data::List(items)=>items.iter().map(Data::item_size).sum(),
+            Data::Map(entries)=>entries.iter().map(|(key,value)|key.size()+value.item_size()).sum(),
+            _=>0,
+        }
+    }
+    /// The size of this value as an item of a list or map.
+    pub(crate) fn item_size(&self)->usize {
+        return size_of::<Data>()+self.size();
+    }
+    /// The size of the item at `index` as counted by [`Data::size`], 0 if there is none.
+    pub(crate) fn size_at(&self,index:&Self)->usize {
+        match self {
+            Data::List(items)=>match index.position(items) {
+                Ok(i)=>items.get(i).map_or(0,Data::item_size),
+                Err(_)=>0,
+            },
+            Data::Map(entries)=>match index.key() {
+                Ok(key)=>entries.get(&key).map_or(0,|value|key.size()+value.item_size()),
+                Err(_)=>0,
+            },
+            _=>0,
+        }
+    }
+    /// The number of items in a list or map, or characters in a string.
+    pub(crate) fn length(&self)->Result<usize,RuntimeErrorKind> {
+        match self {
+            Data::List(items)=>Ok(items.len()),
+            Data::Map(entries)=>Ok(entries.len()),
+            Data::Str(s)=>Ok(s.chars().count()),
+            data=>Err(RuntimeErrorKind::InvalidOperand{operation:"#",found:data.type_name()}),
+        }
+    }
+}
+
+
+/// `operation` applied to two ints, failing instead of wrapping around.
+fn int_arithmetic(operation:&'static str,n1:i64,n2:i64)->Result<i64,RuntimeErrorKind> {
+    let checked=match operation {
+        "+"=>n1.checked_add(n2),
+        "-"=>n1.checked_sub(n2),
+        "*"=>n1.checked_mul(n2),
+        "/"=>n1.checked_div(n2),
+        "//"=>n1.checked_rem(n2),
+        _=>unreachable!("unknown arithmetic operation `{}`",operation),
+    };
+    return match checked {
+        Some(n)=>Ok(n),
+        None if n2==0&&matches!(operation,"/"|"//")=>Err(RuntimeErrorKind::DivisionByZero),
+        None=>Err(RuntimeErrorKind::Overflow(operation)),
+    };
+}
+/// Compare an int to a number exactly, without rounding the int to a number first, so
+/// `9007199254740993` isn't equal to `9007199254740992.0`. `None` if the number is NaN.
+fn compare_int(n1:i64,n2:f64)->Option<Ordering> {
+    // 2^63, the first number past the biggest int. Every number outside the ints is a whole one.
+    const END:f64=9223372036854775808.0;
+    if n2.is_nan() {
+        return None;
+    }
+    if n2>=END {
+        return Some(Ordering::Less);
+    }
+    if n2< -END {
+        return Some(Ordering::Greater);
+    }
+    let whole=n2.trunc();
+    return match n1.cmp(&(whole as i64)) {
+        // Equal to the whole part, so the fraction decides.
+        Ordering::Equal=>0.0.partial_cmp(&(n2-whole)),
+        ordering=>Some(ordering),
+    };
+}
+/// `operation` applied to two numbers.
+fn float_arithmetic(operation:&'static str,n1:f64,n2:f64)->Result<f64,RuntimeErrorKind> {
+    return match operation {
+        "+"=>Ok(n1+n2),
+        "-"=>Ok(n1-n2),
+        "*"=>Ok(n1*n2),
+        "/"|"//" if n2==0.0=>Err(RuntimeErrorKind::DivisionByZero),
+        "/"=>Ok(n1/n2),
+        "//"=>Ok(n1%n2),
+        _=>unreachable!("unknown arithmetic operation `{}`",operation),
+    };
+}
+
+
+/// A map key. Ints and numbers sort before strings, so maps always iterate in the same order.
+#[derive(Clone,Debug)]
+pub enum Key {
+    Int(i64),
+    Number(f64),
+    Str(String),
+}
+impl Key {
+    /// The key for a number or string, `None` for anything else and NaN.
+    pub fn new(data:&Data)->Option<Self> {
+        match data {
+            Data::Int(n)=>Some(Key::Int(*n)),
+            Data::Number(n) if n.is_nan()=>Option::None,
+            // `1.0` equals `1`, so whole numbers have to be the same key as the int
+            Data::Number(n) if n.fract()==0.0&&*n>=i64::MIN as f64&&*n<i64::MAX as f64=>Some(Key::Int(*n as i64)),
+            Data::Number(n)=>Some(Key::Number(*n)),
+            Data::Str(s)=>Some(Key::Str(s.clone())),
+            _=>Option::None,
+        }
+    }
+    /// The size of the key as part of a map entry, see [`Data::size`].
+    fn size(&self)->usize {
+        match self {
+            Key::Str(s)=>size_of::<Key>()+s.len(),
+            _=>size_of::<Key>(),
+        }
+    }
+    pub fn to_data<'doc>(&self)->Data<'doc> {
+        match self {
+            Key::Int(n)=>Data::Int(*n),
+            Key::Number(n)=>Data::Number(*n),
+            Key::Str(s)=>Data::Str(s.clone()),
+        }
+    }
+}
+impl Display for Key {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        match self {
+            Key::Int(n)=>write!(f,"{}",n),
+            Key::Number(n)=>write!(f,"{}",n),
+            Key::Str(s)=>write!(f,"{:?}",s),
+        }
+    }
+}
+impl PartialEq for Key {
+    fn eq(&self,other:&Self)->bool {
+        self.cmp(other)==Ordering::Equal
+    }
+}
+impl Eq for Key {}
+impl PartialOrd for Key {
+    fn partial_cmp(&self,other:&Self)->Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+impl Ord for Key {
+    fn cmp(&self,other:&Self)->Ordering {
+        use Key::*;
+        match (self,other) {
+            (Int(n1),Int(n2))=>n1.cmp(n2),
+            (Number(n1),Number(n2))=>n1.total_cmp(n2),
+            (Int(n1),Number(n2))=>compare_int(*n1,*n2).unwrap_or_else(||(*n1 as f64).total_cmp(n2)),
+            (Number(n1),Int(n2))=>compare_int(*n2,*n1).map_or_else(||n1.total_cmp(&(*n2 as f64)),Ordering::reverse),
+            (Str(s1),Str(s2))=>s1.cmp(s2),
+            (Str(_),_)=>Ordering::Greater,
+            (_,Str(_))=>Ordering::Less,
+        }
+    }
+}
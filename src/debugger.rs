@@ -0,0 +1,329 @@
+use generic_parser::GenericParser;
+use std::{
+    io::{
+        BufRead,
+        Write,
+        stdin,
+        stdout,
+    },
+    slice,
+};
+use crate::{
+    ast::{
+        Operation,
+        OperationKind,
+        Program,
+    },
+    error::RuntimeErrorKind,
+    interpreter::{
+        Hook,
+        Interpreter,
+    },
+    parser::{
+        Parser,
+        ErrorKind,
+    },
+    span::Source,
+};
+
+
+const HELP:&str="\
+Commands:
+    b, break [<class>><Function>|<line>]   Add a breakpoint, or list them without an argument
+    d, delete <class>><Function>|<line>    Remove a breakpoint
+    s, step                                Run the next operation, stepping into calls
+    n, next                                Run the next operation, stepping over calls
+    o, out                                 Run until the current function returns
+    c, continue                            Run until the next breakpoint
+    bt, stack                              Show the call stack
+    p, print [<variable>]                  Show a variable, or every variable in scope
+    set <variable>=<data>                  Change a variable in the current scope
+    w, where                               Show the current operation
+    q, quit                                Stop the program
+    h, help                                Show this message";
+
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum Breakpoint {
+    /// Pause when the function starts.
+    Function(u32,String),
+    /// Pause at the first operation on the line.
+    Line(usize),
+}
+impl Breakpoint {
+    fn parse(text:&str)->Option<Self> {
+        if let Ok(line)=text.parse() {
+            return Some(Breakpoint::Line(line));
+        }
+        let (class,function)=text.split_once('>')?;
+        let function=function.trim();
+        if function.len()<1||!function.chars().all(|c|c.is_ascii_uppercase()) {
+            return None;
+        }
+        return Some(Breakpoint::Function(class.trim().parse().ok()?,function.to_string()));
+    }
+}
+
+
+/// When to pause next.
+#[derive(Clone,Copy,Debug,PartialEq)]
+enum Mode {
+    /// Only at breakpoints.
+    Continue,
+    /// At the next operation.
+    Step,
+    /// At the next operation with at most this many functions on the stack.
+    Depth(usize),
+}
+
+
+fn io_error(e:std::io::Error)->RuntimeErrorKind {
+    RuntimeErrorKind::Io(e.to_string())
+}
+
+
+/// A [`Hook`] that pauses the program at breakpoints and reads commands, from stdin unless
+/// [`Debugger::set_input`] gives it something else. It pauses at the first operation so
+/// breakpoints can be set before the program runs.
+pub struct Debugger<'doc> {
+    source:&'doc str,
+    /// `None` reads from the real stdin, which is locked for every command so the program can
+    /// read from it too.
+    input:Option<Box<dyn BufRead+'doc>>,
+    output:Box<dyn Write+'doc>,
+    /// Every variable name the program uses, so `set` can give a variable a name that lives as
+    /// long as the program.
+    names:Vec<&'doc str>,
+    breakpoints:Vec<Breakpoint>,
+    mode:Mode,
+    /// A function breakpoint was hit, pause at its first operation.
+    entered:bool,
+    /// The line, offset and depth of the last operation. A line breakpoint pauses once per visit
+    /// to its line, and again for every round of a loop on it.
+    last:(usize,usize,usize),
+}
+impl<'doc> Debugger<'doc> {
+    pub fn new(program:&Program<'doc>,source:&'doc str)->Self {
+        let mut names=Vec::new();
+        for class in program.classes.values() {
+            for function in class.functions.values() {
+                names.extend(function.parameters.iter());
+                collect_names(&function.operations,&mut names);
+            }
+        }
+        Debugger {
+            source,
+            input:None,
+            output:Box::new(stdout()),
+            names,
+            breakpoints:Vec::new(),
+            mode:Mode::Step,
+            entered:false,
+            last:(0,0,0),
+        }
+    }
+    pub fn set_input<R:BufRead+'doc>(&mut self,input:R) {
+        self.input=Some(Box::new(input));
+    }
+    pub fn set_output<W:Write+'doc>(&mut self,output:W) {
+        self.output=Box::new(output);
+    }
+    pub fn add_breakpoint(&mut self,breakpoint:Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+    fn show(&mut self,operation:&Operation<'doc>,interpreter:&Interpreter<'doc>)->std::io::Result<()> {
+        let span=operation.span;
+        match interpreter.stack().last() {
+            Some(frame)=>writeln!(self.output,"In {} at {}:{}:{}",frame,span.file,span.line,span.column)?,
+            None=>writeln!(self.output,"At {}:{}:{}",span.file,span.line,span.column)?,
+        }
+        let line=self.source.lines().nth(span.line-1).unwrap_or("");
+        writeln!(self.output,"{:>4} | {}",span.line,line)?;
+        return writeln!(self.output,"     | {:>column$}^","",column=span.column-1);
+    }
+    fn print_stack(&mut self,interpreter:&Interpreter<'doc>)->std::io::Result<()> {
+        if interpreter.stack().len()==0 {
+            writeln!(self.output,"At the top level")?;
+        }
+        for (i,frame) in interpreter.stack().iter().rev().enumerate() {
+            match frame.called_at {
+                Some(at)=>writeln!(self.output,"#{} {} called at {}:{}:{}",i,frame,at.file,at.line,at.column)?,
+                None=>writeln!(self.output,"#{} {} called from the top level",i,frame)?,
+            }
+        }
+        return Ok(());
+    }
+    fn print_variables(&mut self,name:Option<&str>,interpreter:&Interpreter<'doc>)->std::io::Result<()> {
+        match name {
+            Some(name)=>match interpreter.variable(name) {
+                Some(data)=>writeln!(self.output,"{} = {}",name,data.quoted())?,
+                None=>writeln!(self.output,"`{}` is not defined",name)?,
+            },
+            None=>{
+                let variables=interpreter.variables();
+                if variables.len()==0 {
+                    writeln!(self.output,"No variables in scope")?;
+                }
+                for (name,data) in variables {
+                    writeln!(self.output,"{} = {}",name,data.quoted())?;
+                }
+            },
+        }
+        return Ok(());
+    }
+    /// Read commands until one of them resumes the program.
+    fn prompt(&mut self,operation:&Operation<'doc>,interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        self.show(operation,interpreter).map_err(io_error)?;
+        loop {
+            write!(self.output,"(debug) ").map_err(io_error)?;
+            self.output.flush().map_err(io_error)?;
+            let mut line=String::new();
+            let read=match &mut self.input {
+                Some(input)=>input.read_line(&mut line),
+                None=>stdin().lock().read_line(&mut line),
+            };
+            if read.map_err(io_error)?==0 {
+                // Nothing left to read, so let the program finish.
+                self.breakpoints.clear();
+                self.mode=Mode::Continue;
+                return writeln!(self.output).map_err(io_error);
+            }
+            let line=line.trim();
+            let (command,argument)=match line.split_once(char::is_whitespace) {
+                Some((command,argument))=>(command,Some(argument.trim())),
+                None=>(line,None),
+            };
+            match (command,argument) {
+                ("b"|"break",None)=>{
+                    if self.breakpoints.len()==0 {
+                        writeln!(self.output,"No breakpoints").map_err(io_error)?;
+                    }
+                    for breakpoint in self.breakpoints.iter() {
+                        let written=match breakpoint {
+                            Breakpoint::Function(class,function)=>writeln!(self.output,"{}>{}",class,function),
+                            Breakpoint::Line(line)=>writeln!(self.output,"line {}",line),
+                        };
+                        written.map_err(io_error)?;
+                    }
+                },
+                ("b"|"break",Some(argument))=>match Breakpoint::parse(argument) {
+                    Some(breakpoint)=>self.add_breakpoint(breakpoint),
+                    None=>writeln!(self.output,"Expected a function like `0>A` or a line number").map_err(io_error)?,
+                },
+                ("d"|"delete",Some(argument))=>match Breakpoint::parse(argument) {
+                    Some(breakpoint)=>self.breakpoints.retain(|b|*b!=breakpoint),
+                    None=>writeln!(self.output,"Expected a function like `0>A` or a line number").map_err(io_error)?,
+                },
+                ("s"|"step",None)=>{
+                    self.mode=Mode::Step;
+                    return Ok(());
+                },
+                ("n"|"next",None)=>{
+                    self.mode=Mode::Depth(interpreter.stack().len());
+                    return Ok(());
+                },
+                ("o"|"out",None)=>{
+                    self.mode=match interpreter.stack().len() {
+                        0=>Mode::Continue,
+                        depth=>Mode::Depth(depth-1),
+                    };
+                    return Ok(());
+                },
+                ("c"|"continue",None)=>{
+                    self.mode=Mode::Continue;
+                    return Ok(());
+                },
+                ("bt"|"stack",None)=>self.print_stack(interpreter).map_err(io_error)?,
+                ("p"|"print",name)=>self.print_variables(name,interpreter).map_err(io_error)?,
+                ("set",Some(argument))=>match argument.split_once('=') {
+                    Some((name,data))=>{
+                        let name=name.trim();
+                        if name.len()<1||!name.chars().all(|c|c.is_ascii_lowercase()) {
+                            writeln!(self.output,"`{}` is not a variable name",name).map_err(io_error)?;
+                            continue;
+                        }
+                        // Variables live as long as the program, so their name has to come from it.
+                        let name=match self.names.iter().find(|variable|**variable==name) {
+                            Some(name)=>*name,
+                            None=>{
+                                writeln!(self.output,"The program doesn't use `{}`",name).map_err(io_error)?;
+                                continue;
+                            },
+                        };
+                        let text=format!("{}\n",data.trim());
+                        let source=Source::new(&text,"<debugger>");
+                        match GenericParser::<ErrorKind>::new(&text,"<debugger>").data(&source) {
+                            Ok(data)=>interpreter.set_variable(name,&data),
+                            Err(e)=>writeln!(self.output,"Could not parse the value: {}",e.kind).map_err(io_error)?,
+                        }
+                    },
+                    None=>writeln!(self.output,"Expected `set <variable>=<data>`").map_err(io_error)?,
+                },
+                ("w"|"where",None)=>self.show(operation,interpreter).map_err(io_error)?,
+                ("q"|"quit",None)=>return Err(RuntimeErrorKind::Stopped),
+                ("h"|"help",None)=>writeln!(self.output,"{}",HELP).map_err(io_error)?,
+                ("",None)=>{},
+                _=>writeln!(self.output,"Unknown command `{}`, try `help`",line).map_err(io_error)?,
+            }
+        }
+    }
+}
+impl<'doc> Hook<'doc> for Debugger<'doc> {
+    fn operation(&mut self,operation:&Operation<'doc>,interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        let depth=interpreter.stack().len();
+        let span=operation.span;
+        let (line,offset,last_depth)=self.last;
+        // Going back to an earlier operation on the same line starts the next round of a loop.
+        let visit=span.line!=line||depth!=last_depth||span.offset<=offset;
+        let on_line=visit&&self.breakpoints.contains(&Breakpoint::Line(span.line));
+        self.last=(span.line,span.offset,depth);
+        let pause=match self.mode {
+            Mode::Step=>true,
+            Mode::Depth(max)=>depth<=max,
+            Mode::Continue=>false,
+        };
+        if pause||on_line||self.entered {
+            self.entered=false;
+            return self.prompt(operation,interpreter);
+        }
+        return Ok(());
+    }
+    fn enter(&mut self,interpreter:&mut Interpreter<'doc>) {
+        if let Some(frame)=interpreter.stack().last() {
+            let breakpoint=Breakpoint::Function(frame.class,frame.function.to_string());
+            if self.breakpoints.contains(&breakpoint) {
+                // `enter` can't fail, a broken output shows up at the next prompt instead.
+                let _=writeln!(self.output,"Breakpoint {}",frame);
+                self.entered=true;
+            }
+        }
+    }
+    fn exit(&mut self,_interpreter:&mut Interpreter<'doc>) {
+        // The function had no operations to pause at.
+        self.entered=false;
+    }
+}
+
+
+/// Every variable `operations` use, including the ones in conditionals and loops.
+fn collect_names<'doc>(operations:&[Operation<'doc>],names:&mut Vec<&'doc str>) {
+    for operation in operations {
+        names.extend(operation.kind.variables());
+        match &operation.kind {
+            OperationKind::Conditional{to_compare,inner,otherwise}=>{
+                collect_names(slice::from_ref(&**to_compare),names);
+                collect_names(inner,names);
+                if let Some(otherwise)=otherwise {
+                    collect_names(otherwise,names);
+                }
+            },
+            OperationKind::Loop{condition,body}=>{
+                collect_names(slice::from_ref(&**condition),names);
+                collect_names(body,names);
+            },
+            _=>{},
+        }
+    }
+}
@@ -0,0 +1,299 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult,
+};
+use crate::{
+    json::Json,
+    parser::ErrorKind,
+    span::Span,
+};
+
+
+#[derive(Clone,Debug,PartialEq)]
+pub enum RuntimeErrorKind {
+    UnknownClass(u32),
+    UnknownFunction {
+        class:u32,
+        function:String,
+        /// A function with a similar name, like `0>MAIN` for `0>MIAN`.
+        suggestion:Option<String>,
+    },
+    ArgumentCount {
+        class:u32,
+        function:String,
+        expected:usize,
+        found:usize,
+    },
+    TypeMismatch {
+        operation:&'static str,
+        left:&'static str,
+        right:&'static str,
+    },
+    InvalidOperand {
+        operation:&'static str,
+        found:&'static str,
+    },
+    DivisionByZero,
+    Overflow(&'static str),
+    OutsideLoop(&'static str),
+    InvalidNumber(String),
+    InvalidIndex(String),
+    IndexOutOfRange {
+        index:usize,
+        length:usize,
+    },
+    EmptyList,
+    InvalidKey(&'static str),
+    MissingKey(String),
+    Io(String),
+    OperationLimit(u64),
+    CallDepthLimit(usize),
+    MemoryLimit(usize),
+    /// A [`Hook`](crate::Hook) stopped the program, like quitting the debugger does.
+    Stopped,
+}
+impl Display for RuntimeErrorKind {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        use RuntimeErrorKind::*;
+        match self {
+            UnknownClass(class)=>write!(f,"Unknown class `{}`",class),
+            UnknownFunction{class,function,..}=>write!(f,"Unknown function `{}>{}`",class,function),
+            ArgumentCount{class,function,expected,found}=>write!(f,"`{}>{}` takes {} argument(s) but {} were given",class,function,expected,found),
+            TypeMismatch{operation,left,right}=>write!(f,"Can't apply `{}` to {} and {}",operation,left,right),
+            InvalidOperand{operation,found}=>write!(f,"Can't apply `{}` to {}",operation,found),
+            DivisionByZero=>write!(f,"Division by zero"),
+            Overflow(operation)=>write!(f,"`{}` overflowed",operation),
+            OutsideLoop(operation)=>write!(f,"`{}` can only be used inside a loop",operation),
+            InvalidNumber(line)=>write!(f,"Expected a number but read `{}`",line),
+            InvalidIndex(index)=>write!(f,"`{}` is not a valid index",index),
+            IndexOutOfRange{index,length}=>write!(f,"Index {} is out of range for a list of length {}",index,length),
+            EmptyList=>write!(f,"Can't pop from an empty list"),
+            InvalidKey(found)=>write!(f,"Map keys have to be numbers or strings, not {}",found),
+            MissingKey(key)=>write!(f,"The map has no key {}",key),
+            Io(e)=>write!(f,"I/O error: {}",e),
+            OperationLimit(max)=>write!(f,"The program ran more than {} operations",max),
+            CallDepthLimit(max)=>write!(f,"Calls were nested more than {} deep",max),
+            MemoryLimit(max)=>write!(f,"Strings, lists and maps used more than {} bytes",max),
+            Stopped=>write!(f,"The program was stopped"),
+        }
+    }
+}
+
+
+impl RuntimeErrorKind {
+    /// The code of the error, like `H0106`. Codes never change, so they can be searched for.
+    pub fn code(&self)->&'static str {
+        use RuntimeErrorKind::*;
+        match self {
+            UnknownClass(_)=>"H0101",
+            UnknownFunction{..}=>"H0102",
+            ArgumentCount{..}=>"H0103",
+            TypeMismatch{..}=>"H0104",
+            InvalidOperand{..}=>"H0105",
+            DivisionByZero=>"H0106",
+            Overflow(_)=>"H0107",
+            OutsideLoop(_)=>"H0108",
+            InvalidNumber(_)=>"H0109",
+            InvalidIndex(_)=>"H0110",
+            IndexOutOfRange{..}=>"H0111",
+            EmptyList=>"H0112",
+            InvalidKey(_)=>"H0113",
+            MissingKey(_)=>"H0114",
+            Io(_)=>"H0115",
+            OperationLimit(_)=>"H0116",
+            CallDepthLimit(_)=>"H0117",
+            MemoryLimit(_)=>"H0118",
+            Stopped=>"H0119",
+        }
+    }
+    /// A note on how to fix the error, if there is one.
+    pub fn help(&self)->Option<String> {
+        use RuntimeErrorKind::*;
+        let help=match self {
+            UnknownClass(_)=>"calls can only go to classes defined in the program",
+            UnknownFunction{suggestion:Some(suggestion),..}=>return Some(format!("did you mean `{}`?",suggestion)),
+            UnknownFunction{..}=>"calls can only go to functions defined in the class they name",
+            ArgumentCount{..}=>"pass one argument for every parameter of the function",
+            TypeMismatch{operation:"+",..}=>"ints and numbers can be added to each other, and strings, lists and maps to their own type",
+            TypeMismatch{operation:"&"|"|",..}=>"`&` and `|` only work on bools",
+            TypeMismatch{operation:"=="|"!=",..}=>"values of any type can be compared with `==` and `!=`",
+            TypeMismatch{..}=>"both sides must be ints or numbers, or both the same type",
+            InvalidOperand{operation:"!",..}=>"`!` only works on bools",
+            InvalidOperand{operation:"#",..}=>"only strings, lists and maps have a length",
+            InvalidOperand{..}=>"only lists and maps have items",
+            DivisionByZero=>"check that the divisor isn't 0 before dividing",
+            Overflow(_)=>"ints are 64 bits, use a number like `1.0` for bigger values",
+            OutsideLoop(_)=>"`@>` and `@<` only work in the body of a loop, not in functions it calls",
+            InvalidNumber(_)=>"`a$#` reads a line holding only a number, `a$` reads any line",
+            InvalidIndex(_)=>"lists are indexed with ints from 0",
+            IndexOutOfRange{length:0,..}=>"the list is empty, check its length with `n=#list` first",
+            IndexOutOfRange{length,..}=>return Some(format!("lists are indexed from 0, so the last item is at {}",length-1)),
+            EmptyList=>"check the length of the list with `n=#list` first",
+            InvalidKey(_)=>"use a number or string as the key",
+            MissingKey(_)=>"check for the key with `a=map[key]?` first",
+            OperationLimit(_)=>"the program may loop forever, otherwise raise the limit with `--max-operations`",
+            CallDepthLimit(_)=>"a function that calls itself needs a case that stops, otherwise raise the limit with `--max-depth`",
+            MemoryLimit(_)=>"the program may grow a value forever, otherwise raise the limit with `--max-memory`",
+            Io(_)|Stopped=>return None,
+        };
+        return Some(help.to_string());
+    }
+}
+
+
+/// An error found while parsing or checking the calls of a program, with where it was found.
+#[derive(Debug,PartialEq)]
+pub struct Diagnostic<'doc> {
+    pub kind:ErrorKind,
+    pub span:Span<'doc>,
+}
+impl<'doc> Diagnostic<'doc> {
+    /// Print the error to stderr with its code, the line of `source` it was found on and a note on
+    /// how to fix it.
+    pub fn print_with_context(&self,source:&str,color:bool) {
+        let (bold,red,reset)=if color {("\x1b[1m","\x1b[1;31m","\x1b[0m")} else {("","","")};
+        let kind=&self.kind;
+        eprintln!("{}Parse error[{}]{}{}: {}{}",red,kind.code(),reset,bold,kind,reset);
+        print_snippet(source,self.span,color);
+        print_help(self.span,&kind.help());
+    }
+    /// The error as a JSON object on one line, for `--error-format=json`. `source` is the text
+    /// that was parsed.
+    pub fn to_json(&self,source:&str)->String {
+        let kind=&self.kind;
+        let json=Json::object([
+            ("type","parse".into()),
+            ("severity","error".into()),
+            ("code",kind.code().into()),
+            ("message",kind.to_string().into()),
+            ("help",kind.help().into()),
+            ("file",self.span.file.into()),
+            ("span",span_json(source,self.span)),
+            ("stack",Json::Array(Vec::new())),
+        ]);
+        return json.to_string();
+    }
+}
+
+
+/// A function that was running when a [`RuntimeError`] happened.
+#[derive(Clone,Debug,PartialEq)]
+pub struct Frame<'doc> {
+    pub class:u32,
+    pub function:&'doc str,
+    /// The call that started this function, `None` for top-level statements.
+    pub called_at:Option<Span<'doc>>,
+}
+impl<'doc> Display for Frame<'doc> {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        write!(f,"{}>{}",self.class,self.function)
+    }
+}
+
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct RuntimeError<'doc> {
+    pub kind:RuntimeErrorKind,
+    /// The operation or top-level call that failed, `None` when nothing in the source did, like
+    /// when flushing the output at the end failed.
+    pub span:Option<Span<'doc>>,
+    /// The call stack at the time of the error, innermost call last.
+    pub stack:Vec<Frame<'doc>>,
+}
+impl<'doc> RuntimeError<'doc> {
+    /// Print the error to stderr with its code, the failing line of `source`, a note on how to fix
+    /// it and a stack trace.
+    pub fn print_with_context(&self,source:&str,color:bool) {
+        let (bold,red,reset)=if color {("\x1b[1m","\x1b[1;31m","\x1b[0m")} else {("","","")};
+        eprintln!("{}Runtime error[{}]{}{}: {}{}",red,self.kind.code(),reset,bold,self.kind,reset);
+        if let Some(span)=self.span {
+            print_snippet(source,span,color);
+            if let Some(help)=self.kind.help() {
+                print_help(span,&help);
+            }
+        }
+        let mut frames=self.stack.iter().rev().peekable();
+        while let Some(frame)=frames.next() {
+            match frame.called_at {
+                Some(at)=>eprintln!("    in {} called at {}:{}:{}",frame,at.file,at.line,at.column),
+                None=>eprintln!("    in {} called from the top level",frame),
+            }
+            // Deep recursion repeats the same frame, so only show it once.
+            let mut repeated=0;
+            while frames.next_if_eq(&frame).is_some() {
+                repeated+=1;
+            }
+            if repeated>0 {
+                eprintln!("    ... the frame above repeats {} more time(s)",repeated);
+            }
+        }
+    }
+}
+impl<'doc> RuntimeError<'doc> {
+    /// The error as a JSON object on one line, for `--error-format=json`. `source` is the text
+    /// of the program.
+    pub fn to_json(&self,source:&str)->String {
+        let stack=self.stack.iter()
+            .rev()
+            .map(|frame|Json::object([
+                ("function",frame.to_string().into()),
+                ("called_at",frame.called_at.map_or(Json::Null,|at|span_json(source,at))),
+            ]))
+            .collect();
+        let json=Json::object([
+            ("type","runtime".into()),
+            ("severity","error".into()),
+            ("code",self.kind.code().into()),
+            ("message",self.kind.to_string().into()),
+            ("help",self.kind.help().map_or(Json::Null,Json::from)),
+            ("file",self.span.map_or(Json::Null,|span|span.file.into())),
+            ("span",self.span.map_or(Json::Null,|span|span_json(source,span))),
+            ("stack",Json::Array(stack)),
+        ]);
+        return json.to_string();
+    }
+}
+impl<'doc> Display for RuntimeError<'doc> {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        write!(f,"{}",self.kind)?;
+        if let Some(span)=self.span {
+            write!(f," at {}:{}:{}",span.file,span.line,span.column)?;
+        }
+        return Ok(());
+    }
+}
+
+
+/// Print where `span` is, the line of `source` it is on and a marker under it.
+fn print_snippet(source:&str,span:Span,color:bool) {
+    let (red,reset)=if color {("\x1b[1;31m","\x1b[0m")} else {("","")};
+    let line=source.lines().nth(span.line-1).unwrap_or("");
+    let gutter=span.line.to_string().len();
+    eprintln!("{:>gutter$}--> {}:{}:{}","",span.file,span.line,span.column,gutter=gutter);
+    eprintln!("{:>gutter$} |","",gutter=gutter);
+    eprintln!("{} | {}",span.line,line);
+    eprintln!("{:>gutter$} | {:>column$}{}^{}","","",red,reset,gutter=gutter,column=span.column-1);
+}
+/// Print a note under the snippet printed for `span`.
+fn print_help(span:Span,help:&str) {
+    let gutter=span.line.to_string().len();
+    eprintln!("{:>gutter$} = help: {}","",help,gutter=gutter);
+}
+/// Where `span` starts and ends in `source`, as a JSON object. Spans only mark where something
+/// starts, so they end after its first character.
+fn span_json(source:&str,span:Span)->Json {
+    let position=|offset:usize,line:usize,column:usize|Json::object([
+        ("offset",offset.into()),
+        ("line",line.into()),
+        ("column",column.into()),
+    ]);
+    let end=match source.get(span.offset..).and_then(|rest|rest.chars().next()) {
+        Some(c) if c!='\n'=>position(span.offset+c.len_utf8(),span.line,span.column+1),
+        _=>position(span.offset,span.line,span.column),
+    };
+    return Json::object([
+        ("start",position(span.offset,span.line,span.column)),
+        ("end",end),
+    ]);
+}
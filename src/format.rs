@@ -0,0 +1,110 @@
+use crate::ast::*;
+
+
+const INDENT:&str="    ";
+
+
+impl<'doc> Program<'doc> {
+    /// Write the program back as source in the indented style of `examples/`: one class, function
+    /// and operation per line with a `,` after every operation, then the top level statements.
+    ///
+    /// Classes and functions keep the order they have in the source. Operations are written as
+    /// they were, only without whitespace outside strings, so strings keep their escapes exactly.
+    pub fn format(&self)->String {
+        let mut out=String::new();
+        let mut classes:Vec<_>=self.classes.iter().collect();
+        classes.sort_by_key(|(_,class)|class.span.offset);
+        for (number,class) in classes {
+            out.push_str(&number.to_string());
+            out.push_str(":\n");
+            let mut functions:Vec<_>=class.functions.iter().collect();
+            functions.sort_by_key(|(_,function)|function.span.offset);
+            for (name,function) in functions {
+                out.push_str(INDENT);
+                out.push_str(name);
+                if function.parameters.len()>0 {
+                    out.push('(');
+                    out.push_str(&function.parameters.join(","));
+                    out.push(')');
+                }
+                out.push_str(":\n");
+                block(&mut out,&function.operations,2);
+                out.push_str(INDENT);
+                out.push_str(";\n");
+            }
+            out.push_str(";\n");
+        }
+        for statement in self.statements.iter() {
+            out.push_str(&compact(statement.text));
+            out.push('\n');
+        }
+        return out;
+    }
+}
+
+
+/// Write every operation on its own line, indented `depth` times.
+fn block(out:&mut String,operations:&[Operation],depth:usize) {
+    for operation in operations {
+        out.push_str(&INDENT.repeat(depth));
+        match &operation.kind {
+            OperationKind::Conditional{to_compare,inner,otherwise}=>{
+                condition(out,to_compare);
+                out.push('?');
+                braced(out,inner,depth);
+                if let Some(otherwise)=otherwise {
+                    out.push(':');
+                    braced(out,otherwise,depth);
+                }
+            },
+            OperationKind::Loop{condition:to_check,body}=>{
+                condition(out,to_check);
+                out.push('@');
+                braced(out,body,depth);
+            },
+            _=>out.push_str(&compact(operation.text)),
+        }
+        out.push_str(",\n");
+    }
+}
+/// Write the operation of a conditional or loop in parentheses, always on one line.
+fn condition(out:&mut String,operation:&Operation) {
+    out.push('(');
+    out.push_str(&compact(operation.text));
+    out.push(')');
+}
+/// Write a block in braces, with the closing brace at the indentation of the operation it is in.
+fn braced(out:&mut String,operations:&[Operation],depth:usize) {
+    if operations.is_empty() {
+        out.push_str("{}");
+        return;
+    }
+    out.push_str("{\n");
+    block(out,operations,depth+1);
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+}
+/// `text` without any whitespace outside of string literals.
+fn compact(text:&str)->String {
+    let mut out=String::with_capacity(text.len());
+    let mut in_string=false;
+    let mut escaped=false;
+    for c in text.chars() {
+        if in_string {
+            out.push(c);
+            if escaped {
+                escaped=false;
+            } else if c=='\\' {
+                escaped=true;
+            } else if c=='"' {
+                in_string=false;
+            }
+        } else if c=='"' {
+            in_string=true;
+            out.push(c);
+        } else if !c.is_whitespace() {
+            out.push(c);
+        }
+    }
+    return out;
+}
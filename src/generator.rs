@@ -0,0 +1,312 @@
+use std::fmt::Write;
+
+
+/// How deeply conditionals and loops nest at most.
+const MAX_DEPTH:usize=3;
+/// How many operations a block, items a list or arguments a call have at most.
+const MAX_ITEMS:usize=5;
+const VARIABLES:&[&str]=&["a","b","c","x","y","list","map"];
+const OPERANDS:&[&str]=&["+","-","*","/","//","==","!=",">","<",">=","<=","&","|","="];
+const INPUTS:&[&str]=&["$","$#","$$","$?"];
+const STRING_PARTS:&[&str]=&["text"," ","\\n","\\t","\\\"","\\\\","\\x41","\\0"];
+
+
+/// Writes random programs that follow `grammar.txt`, for fuzzing the parser and the interpreter.
+///
+/// Every choice is read from the next byte of `choices`, so a fuzzer can steer what gets written.
+/// Once the bytes run out the first alternative is always picked, which is the shortest one, so
+/// writing a program always ends. Calls always target functions of the program with an argument
+/// for every parameter, so the programs pass [`Program::link`](crate::Program::link) and do
+/// something when they run.
+#[derive(Debug)]
+pub struct Generator<'a> {
+    choices:&'a [u8],
+    /// The class, name and parameter count of every function.
+    functions:Vec<(u32,String,usize)>,
+    out:String,
+    depth:usize,
+}
+impl<'a> Generator<'a> {
+    pub fn new(choices:&'a [u8])->Self {
+        Generator {
+            choices,
+            functions:Vec::new(),
+            out:String::new(),
+            depth:0,
+        }
+    }
+    /// Write a whole program.
+    pub fn program(mut self)->String {
+        let classes=1+self.choose(3) as u32;
+        for class in 0..classes {
+            for i in 0..1+self.choose(3) {
+                let parameters=self.choose(3);
+                self.functions.push((class,function_name(i),parameters));
+            }
+        }
+        for class in 0..classes {
+            let _=writeln!(self.out,"{}:",class);
+            let functions:Vec<_>=self.functions.iter()
+                .filter(|(c,..)|*c==class)
+                .map(|(_,name,parameters)|(name.clone(),*parameters))
+                .collect();
+            for (name,parameters) in functions {
+                self.function(&name,parameters);
+            }
+            self.out.push_str(";\n");
+        }
+        for _ in 0..1+self.choose(2) {
+            self.call();
+            self.out.push('\n');
+        }
+        return self.out;
+    }
+    /// The next choice out of `n`.
+    fn choose(&mut self,n:usize)->usize {
+        match self.choices.split_first() {
+            Some((choice,rest))=>{
+                self.choices=rest;
+                return *choice as usize%n;
+            },
+            None=>return 0,
+        }
+    }
+    fn pick(&mut self,options:&[&'static str])->&'static str {
+        return options[self.choose(options.len())];
+    }
+    fn variable(&mut self) {
+        let name=self.pick(VARIABLES);
+        self.out.push_str(name);
+    }
+    fn function(&mut self,name:&str,parameters:usize) {
+        let _=write!(self.out,"    {}",name);
+        if parameters>0 {
+            self.out.push('(');
+            self.out.push_str(&VARIABLES[..parameters].join(","));
+            self.out.push(')');
+        }
+        self.out.push_str(":\n");
+        self.block(2);
+        self.out.push_str("    ;\n");
+    }
+    /// Operations separated by commas, each on its own line.
+    fn block(&mut self,indent:usize) {
+        for _ in 0..self.choose(MAX_ITEMS+1) {
+            self.out.push_str(&"    ".repeat(indent));
+            self.operation(indent);
+            self.out.push_str(",\n");
+        }
+    }
+    /// A block in braces, like the body of a loop.
+    fn braced_block(&mut self,indent:usize) {
+        self.out.push_str("{\n");
+        self.depth+=1;
+        self.block(indent+1);
+        self.depth-=1;
+        self.out.push_str(&"    ".repeat(indent));
+        self.out.push('}');
+    }
+    fn operation(&mut self,indent:usize) {
+        let alternatives=if self.depth<MAX_DEPTH {19} else {17};
+        match self.choose(alternatives) {
+            0=>{
+                self.variable();
+                self.out.push('=');
+                self.data();
+            },
+            1=>{
+                self.variable();
+                let operand=self.pick(OPERANDS);
+                self.out.push_str(operand);
+                self.variable();
+            },
+            2=>self.variable(),
+            3=>{
+                self.variable();
+                self.out.push('!');
+            },
+            4=>{
+                self.variable();
+                let input=self.pick(INPUTS);
+                self.out.push_str(input);
+            },
+            5=>{
+                self.variable();
+                self.out.push('=');
+                self.call();
+            },
+            choice@(6|7)=>{
+                self.variable();
+                self.out.push('=');
+                self.variable();
+                self.out.push('[');
+                self.data();
+                self.out.push(']');
+                if choice==7 {
+                    self.out.push('?');
+                }
+            },
+            8=>{
+                self.variable();
+                self.out.push('=');
+                self.variable();
+                self.out.push_str("[]");
+            },
+            9=>{
+                self.variable();
+                self.out.push_str("=#");
+                self.variable();
+            },
+            10=>{
+                self.variable();
+                self.out.push('~');
+                self.data();
+            },
+            11=>{
+                self.variable();
+                self.out.push('[');
+                self.data();
+                self.out.push_str("]=");
+                self.data();
+            },
+            12=>{
+                self.variable();
+                self.out.push_str("<<");
+                self.data();
+            },
+            13=>{
+                self.variable();
+                self.out.push_str(">>");
+                self.variable();
+            },
+            14=>{
+                self.out.push('^');
+                if self.choose(2)==1 {
+                    self.data();
+                }
+            },
+            15=>self.call(),
+            16=>{
+                let control=self.pick(&["@>","@<"]);
+                self.out.push_str(control);
+            },
+            17=>{
+                self.condition();
+                self.out.push('?');
+                self.braced_block(indent);
+                if self.choose(2)==1 {
+                    self.out.push(':');
+                    self.braced_block(indent);
+                }
+            },
+            _=>{
+                self.condition();
+                self.out.push('@');
+                self.braced_block(indent);
+            },
+        }
+    }
+    /// The operation in parentheses before a conditional or loop, which never nests blocks.
+    fn condition(&mut self) {
+        self.out.push('(');
+        self.variable();
+        match self.choose(3) {
+            0=>{
+                self.out.push('=');
+                self.data();
+            },
+            1=>{
+                let operand=self.pick(OPERANDS);
+                self.out.push_str(operand);
+                self.variable();
+            },
+            _=>self.out.push('!'),
+        }
+        self.out.push(')');
+    }
+    fn call(&mut self) {
+        let i=self.choose(self.functions.len());
+        let (class,name,arguments)=self.functions[i].clone();
+        let _=write!(self.out,"{}>{}",class,name);
+        if arguments>0 {
+            self.out.push('(');
+            for i in 0..arguments {
+                if i>0 {
+                    self.out.push(',');
+                }
+                self.data();
+            }
+            self.out.push(')');
+        }
+    }
+    fn data(&mut self) {
+        let alternatives=if self.depth<MAX_DEPTH {8} else {6};
+        match self.choose(alternatives) {
+            0=>{
+                let n=self.choose(256) as i64-128;
+                let _=write!(self.out,"{}",n);
+            },
+            1=>{
+                let whole=self.choose(100);
+                let fraction=self.choose(100);
+                let _=write!(self.out,"{}.{}",whole,fraction);
+            },
+            2=>self.string(),
+            3=>self.out.push_str("true"),
+            4=>self.out.push_str("false"),
+            5=>self.variable(),
+            6=>{
+                self.depth+=1;
+                self.out.push('[');
+                for i in 0..self.choose(MAX_ITEMS+1) {
+                    if i>0 {
+                        self.out.push(',');
+                    }
+                    self.data();
+                }
+                self.out.push(']');
+                self.depth-=1;
+            },
+            _=>{
+                self.depth+=1;
+                self.out.push('{');
+                for i in 0..self.choose(MAX_ITEMS+1) {
+                    if i>0 {
+                        self.out.push(',');
+                    }
+                    if self.choose(2)==0 {
+                        let key=self.choose(256);
+                        let _=write!(self.out,"{}",key);
+                    } else {
+                        self.string();
+                    }
+                    self.out.push(':');
+                    self.data();
+                }
+                self.out.push('}');
+                self.depth-=1;
+            },
+        }
+    }
+    fn string(&mut self) {
+        self.out.push('"');
+        for _ in 0..self.choose(MAX_ITEMS) {
+            let part=self.pick(STRING_PARTS);
+            self.out.push_str(part);
+        }
+        self.out.push('"');
+    }
+}
+
+
+/// The name of the `i`th function of a class: `A` to `Z`, then `AA` and so on.
+fn function_name(mut i:usize)->String {
+    let mut name=String::new();
+    loop {
+        name.insert(0,(b'A'+(i%26) as u8) as char);
+        if i<26 {
+            return name;
+        }
+        i=i/26-1;
+    }
+}
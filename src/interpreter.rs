@@ -0,0 +1,576 @@
+use std::{
+    fmt::{
+        Debug,
+        Formatter,
+        Result as FmtResult,
+    },
+    io::{
+        BufRead,
+        Write,
+    },
+    collections::HashMap,
+};
+use crate::{
+    ast::*,
+    data::Data,
+    error::*,
+    span::Span,
+    streams::Streams,
+    suggest::similar_function,
+};
+
+
+type RunResult<'doc,T>=std::result::Result<T,RuntimeError<'doc>>;
+
+
+/// Stops the operations of a block from running, set by operations like `^`.
+#[derive(Debug)]
+enum Flow<'doc> {
+    Return(Data<'doc>),
+    Break(Span<'doc>),
+    Continue(Span<'doc>),
+}
+
+
+/// Watches a program run by the [`Interpreter`], like a debugger does.
+pub trait Hook<'doc> {
+    /// Called before every operation, including the condition of a conditional or loop. Returning
+    /// an error stops the program with it.
+    fn operation(&mut self,_operation:&Operation<'doc>,_interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        Ok(())
+    }
+    /// Called after an operation ran without an error, with the value it produced.
+    fn operation_done(&mut self,_operation:&Operation<'doc>,_result:&Data<'doc>,_interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        Ok(())
+    }
+    /// Called once the frame of a function is on [`Interpreter::stack`], before it runs.
+    fn enter(&mut self,_interpreter:&mut Interpreter<'doc>) {}
+    /// Called when a function is done, while its frame is still on the stack.
+    fn exit(&mut self,_interpreter:&mut Interpreter<'doc>) {}
+}
+impl<'doc> Debug for dyn Hook<'doc>+'doc {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        write!(f,"Hook")
+    }
+}
+
+
+/// How deep calls can nest by default. Every call takes a few native stack frames, so without a
+/// limit deep recursion overflows the native stack and aborts the whole process. This is low
+/// enough for the smallest stacks the interpreter runs on, like the 2 MiB of a spawned thread in
+/// a debug build.
+const DEFAULT_CALL_DEPTH:usize=100;
+
+
+/// Caps on what a program may use, so untrusted programs are stopped with a [`RuntimeError`]
+/// instead of running forever or taking the process down. `None` means unlimited.
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub struct Limits {
+    /// How many operations may run, counting the conditions of conditionals and loops.
+    pub operations:Option<u64>,
+    /// How deeply function calls may nest.
+    pub call_depth:Option<usize>,
+    /// How many bytes all strings, lists and maps in scope may use together, see [`Data::size`].
+    pub memory:Option<usize>,
+}
+impl Default for Limits {
+    fn default()->Self {
+        Limits {
+            operations:None,
+            call_depth:Some(DEFAULT_CALL_DEPTH),
+            memory:None,
+        }
+    }
+}
+
+
+/// The state of a running program. Reuse one across several [`Program::run_with`] calls to keep
+/// the top-level scope alive between them.
+#[derive(Debug,Default)]
+pub struct Interpreter<'doc> {
+    scopes:Vec<HashMap<&'doc str,Data<'doc>>>,
+    stack:Vec<Frame<'doc>>,
+    flow:Option<Flow<'doc>>,
+    hook:Option<Box<dyn Hook<'doc>+'doc>>,
+    /// Holds the value of the last operation while a hook is looking at it.
+    result:Data<'doc>,
+    limits:Limits,
+    /// How many operations ran so far.
+    operations:u64,
+    /// The memory used by variables, only counted when it is limited.
+    memory:usize,
+    streams:Streams<'doc>,
+}
+impl<'doc> Interpreter<'doc> {
+    pub fn new()->Self {
+        Self::default()
+    }
+    /// An interpreter that calls `hook` while running.
+    pub fn with_hook<H:Hook<'doc>+'doc>(hook:H)->Self {
+        Interpreter {
+            hook:Some(Box::new(hook)),
+            ..Self::default()
+        }
+    }
+    pub fn set_limits(&mut self,limits:Limits) {
+        self.limits=limits;
+    }
+    pub fn limits(&self)->Limits {
+        return self.limits;
+    }
+    /// Read input operations from `input` instead of stdin.
+    pub fn set_stdin<R:BufRead+'doc>(&mut self,input:R) {
+        self.streams.set_stdin(input);
+    }
+    /// Write printed values to `output` instead of stdout.
+    pub fn set_stdout<W:Write+'doc>(&mut self,output:W) {
+        self.streams.set_stdout(output);
+    }
+    /// Send what is written to [`Interpreter::stderr`] to `output` instead of stderr.
+    pub fn set_stderr<W:Write+'doc>(&mut self,output:W) {
+        self.streams.set_stderr(output);
+    }
+    /// Where the program prints to, for hooks that want to add to its output.
+    pub fn stdout(&mut self)->&mut (dyn Write+'doc) {
+        return &mut *self.streams.stdout;
+    }
+    /// Where hooks can report on the program without mixing it into its output.
+    pub fn stderr(&mut self)->&mut (dyn Write+'doc) {
+        return &mut *self.streams.stderr;
+    }
+    /// Look up a variable in the innermost scope.
+    pub fn variable(&self,name:&str)->Option<&Data<'doc>> {
+        return self.scopes.last()?.get(name);
+    }
+    /// Every variable in the innermost scope, sorted by name.
+    pub fn variables(&self)->Vec<(&'doc str,&Data<'doc>)> {
+        let mut variables:Vec<_>=self.scopes.last()
+            .map(|scope|scope.iter().map(|(name,data)|(*name,data)).collect())
+            .unwrap_or_default();
+        variables.sort_by_key(|(name,_)|*name);
+        return variables;
+    }
+    /// Change a variable in the innermost scope. Variables in `data` are looked up first, so it
+    /// doesn't have to borrow the program, and ones that aren't set are none.
+    pub fn set_variable(&mut self,name:&'doc str,data:&Data) {
+        let data=self.look_up(data);
+        self.replace(name,data);
+    }
+    /// The functions that are running, innermost call last.
+    pub fn stack(&self)->&[Frame<'doc>] {
+        return &self.stack;
+    }
+    fn scope(&mut self)->&mut HashMap<&'doc str,Data<'doc>> {
+        if self.scopes.len()==0 {
+            self.scopes.push(HashMap::new());
+        }
+        return self.scopes.last_mut().unwrap();
+    }
+    /// Look up the value of `data` if it names a variable, including the items of a list.
+    fn resolve(&mut self,data:&Data<'doc>)->Data<'doc> {
+        match data {
+            Data::Var(name)=>self.scope().entry(name).or_insert(Data::default()).clone(),
+            Data::List(items)=>Data::List(items.iter().map(|item|self.resolve(item)).collect()),
+            Data::Map(entries)=>Data::Map(entries.iter().map(|(key,value)|(key.clone(),self.resolve(value))).collect()),
+            data=>data.clone(),
+        }
+    }
+    /// Like [`Interpreter::resolve`] for data from outside the program, without defining anything.
+    fn look_up(&self,data:&Data)->Data<'doc> {
+        match data {
+            Data::Var(name)=>self.variable(name).cloned().unwrap_or_default(),
+            Data::Int(n)=>Data::Int(*n),
+            Data::Number(n)=>Data::Number(*n),
+            Data::Str(s)=>Data::Str(s.clone()),
+            Data::Bool(b)=>Data::Bool(*b),
+            Data::List(items)=>Data::List(items.iter().map(|item|self.look_up(item)).collect()),
+            Data::Map(entries)=>Data::Map(entries.iter().map(|(key,value)|(key.clone(),self.look_up(value))).collect()),
+            Data::None=>Data::None,
+        }
+    }
+    /// Set `var` in the innermost scope, keeping count of the memory it uses.
+    fn replace(&mut self,var:&'doc str,data:Data<'doc>) {
+        let added=self.measure_data(&data);
+        let old=self.scope().insert(var,data);
+        let removed=old.map_or(0,|old|self.measure_data(&old));
+        self.grow(added,removed);
+    }
+    /// Like [`Interpreter::replace`], failing at `span` if the variables now use more memory than
+    /// allowed.
+    fn assign(&mut self,var:&'doc str,data:Data<'doc>,span:Span<'doc>)->RunResult<'doc,&Data<'doc>> {
+        self.replace(var,data);
+        self.check_memory(span)?;
+        return Ok(self.scopes.last().unwrap().get(var).unwrap());
+    }
+    /// The size of `data` if memory is limited, otherwise 0 without looking at it.
+    fn measure_data(&self,data:&Data<'doc>)->usize {
+        if self.limits.memory.is_none() {
+            return 0;
+        }
+        return data.size();
+    }
+    /// Like [`Interpreter::measure_data`] for an item of a list.
+    fn measure_item(&self,data:&Data<'doc>)->usize {
+        if self.limits.memory.is_none() {
+            return 0;
+        }
+        return data.item_size();
+    }
+    /// Like [`Interpreter::measure_data`] for part of a variable, like a single item.
+    fn measure<M:FnOnce(&Data<'doc>)->usize>(&mut self,var:&'doc str,measure:M)->usize {
+        if self.limits.memory.is_none() {
+            return 0;
+        }
+        return self.scope().get(var).map_or(0,measure);
+    }
+    fn grow(&mut self,added:usize,removed:usize) {
+        self.memory=(self.memory+added).saturating_sub(removed);
+    }
+    /// An error if the variables use more memory than allowed.
+    fn check_memory(&self,span:Span<'doc>)->RunResult<'doc,()> {
+        match self.limits.memory {
+            Some(max) if self.memory>max=>Err(self.error(RuntimeErrorKind::MemoryLimit(max),Some(span))),
+            _=>Ok(()),
+        }
+    }
+    fn error(&self,kind:RuntimeErrorKind,span:Option<Span<'doc>>)->RuntimeError<'doc> {
+        RuntimeError {
+            kind,
+            span,
+            stack:self.stack.clone(),
+        }
+    }
+    /// Flush the buffered output once a run is over, even if it failed.
+    fn finish<T>(&mut self,result:RunResult<'doc,T>)->RunResult<'doc,T> {
+        let flushed=self.streams.flush();
+        let value=result?;
+        flushed.map_err(|kind|self.error(kind,None))?;
+        return Ok(value);
+    }
+    /// Apply `apply` to the first variable with the value of the second one, like `a+b` does.
+    fn apply<F>(&mut self,vars:&[&'doc str;2],span:Span<'doc>,apply:F)->RunResult<'doc,&Data<'doc>>
+    where F:FnOnce(&mut Data<'doc>,Data<'doc>)->std::result::Result<(),RuntimeErrorKind> {
+        let data=self.scope().get(vars[1]).cloned().unwrap_or_default();
+        return self.update(vars[0],span,Data::size,|var|apply(var,data));
+    }
+    /// Change the value of `var` in place. When memory is limited, `measure` is called before and
+    /// after to see how much the part of the variable that changed grew.
+    fn update<M,F>(&mut self,var:&'doc str,span:Span<'doc>,measure:M,update:F)->RunResult<'doc,&Data<'doc>>
+    where M:Fn(&Data<'doc>)->usize,F:FnOnce(&mut Data<'doc>)->std::result::Result<(),RuntimeErrorKind> {
+        let measures=self.limits.memory.is_some();
+        let data=self.scope().entry(var).or_insert(Default::default());
+        let before=if measures {measure(data)} else {0};
+        if let Err(kind)=update(data) {
+            return Err(self.error(kind,Some(span)));
+        }
+        if measures {
+            let after=measure(self.scopes.last().unwrap().get(var).unwrap());
+            self.grow(after,before);
+            self.check_memory(span)?;
+        }
+        return Ok(self.scopes.last().unwrap().get(var).unwrap());
+    }
+    /// Like [`Interpreter::update`] for changes whose size is known before they are made, like
+    /// pushing an item. The memory only changes if `update` succeeds.
+    fn resize<F>(&mut self,var:&'doc str,span:Span<'doc>,added:usize,removed:usize,update:F)->RunResult<'doc,&Data<'doc>>
+    where F:FnOnce(&mut Data<'doc>)->std::result::Result<(),RuntimeErrorKind> {
+        let data=self.scope().entry(var).or_insert(Default::default());
+        if let Err(kind)=update(data) {
+            return Err(self.error(kind,Some(span)));
+        }
+        self.grow(added,removed);
+        self.check_memory(span)?;
+        return Ok(self.scopes.last().unwrap().get(var).unwrap());
+    }
+}
+
+
+impl<'doc> Program<'doc> {
+    fn run_function(&self,call:&FunctionCall<'doc>,called_at:Option<Span<'doc>>,interpreter:&mut Interpreter<'doc>)->RunResult<'doc,Data<'doc>> {
+        let FunctionCall{class,function,arguments,span,..}=call;
+        // Top-level statements have no operation calling them, so their errors point at the call.
+        let at=Some(called_at.unwrap_or(*span));
+        let function_ref=match self.classes.get(class) {
+            Some(c)=>match c.functions.get(function) {
+                Some(f)=>f,
+                None=>{
+                    let kind=RuntimeErrorKind::UnknownFunction {
+                        class:*class,
+                        function:function.to_string(),
+                        suggestion:similar_function(&self.classes,*class,function),
+                    };
+                    return Err(interpreter.error(kind,at));
+                },
+            },
+            None=>return Err(interpreter.error(RuntimeErrorKind::UnknownClass(*class),at)),
+        };
+        if function_ref.parameters.len()!=arguments.len() {
+            let kind=RuntimeErrorKind::ArgumentCount {
+                class:*class,
+                function:function.to_string(),
+                expected:function_ref.parameters.len(),
+                found:arguments.len(),
+            };
+            return Err(interpreter.error(kind,at));
+        }
+        if let Some(max)=interpreter.limits.call_depth {
+            if interpreter.stack.len()>=max {
+                return Err(interpreter.error(RuntimeErrorKind::CallDepthLimit(max),at));
+            }
+        }
+        let mut scope=HashMap::new();
+        let mut added=0;
+        for (name,argument) in function_ref.parameters.iter().zip(arguments) {
+            let data=interpreter.resolve(&argument.data);
+            added+=interpreter.measure_data(&data);
+            scope.insert(*name,data);
+        }
+        interpreter.grow(added,0);
+        if let Err(e)=interpreter.check_memory(called_at.unwrap_or(*span)) {
+            interpreter.grow(0,added);
+            return Err(e);
+        }
+        interpreter.stack.push(Frame{class:*class,function,called_at});
+        interpreter.scopes.push(scope);
+        if let Some(mut hook)=interpreter.hook.take() {
+            hook.enter(interpreter);
+            interpreter.hook=Some(hook);
+        }
+        let result=self.run_block(&function_ref.operations,interpreter);
+        if let Some(mut hook)=interpreter.hook.take() {
+            hook.exit(interpreter);
+            interpreter.hook=Some(hook);
+        }
+        if let Some(scope)=interpreter.scopes.pop() {
+            let removed=scope.values().map(|data|interpreter.measure_data(data)).sum();
+            interpreter.grow(0,removed);
+        }
+        let result=match (result,interpreter.flow.take()) {
+            (Err(e),_)=>Err(e),
+            (Ok(()),Some(Flow::Return(data)))=>Ok(data),
+            (Ok(()),Some(Flow::Break(span)))=>Err(interpreter.error(RuntimeErrorKind::OutsideLoop("@>"),Some(span))),
+            (Ok(()),Some(Flow::Continue(span)))=>Err(interpreter.error(RuntimeErrorKind::OutsideLoop("@<"),Some(span))),
+            (Ok(()),None)=>Ok(Data::None),
+        };
+        interpreter.stack.pop();
+        return result;
+    }
+    /// Run `operations` until one of them fails or changes the control flow.
+    fn run_block(&self,operations:&[Operation<'doc>],interpreter:&mut Interpreter<'doc>)->RunResult<'doc,()> {
+        for op in operations {
+            self.run_operation(op,interpreter)?;
+            if interpreter.flow.is_some() {
+                break;
+            }
+        }
+        return Ok(());
+    }
+    fn run_operation<'int>(&self,operation:&Operation<'doc>,interpreter:&'int mut Interpreter<'doc>)->RunResult<'doc,&'int Data<'doc>> {
+        interpreter.operations+=1;
+        if let Some(max)=interpreter.limits.operations {
+            if interpreter.operations>max {
+                return Err(interpreter.error(RuntimeErrorKind::OperationLimit(max),Some(operation.span)));
+            }
+        }
+        if interpreter.hook.is_none() {
+            return self.execute_operation(operation,interpreter);
+        }
+        let span=Some(operation.span);
+        // Hooks like the debugger print too, so the program's output has to come first.
+        interpreter.streams.flush().map_err(|kind|interpreter.error(kind,span))?;
+        let mut hook=interpreter.hook.take().unwrap();
+        let result=hook.operation(operation,interpreter);
+        interpreter.hook=Some(hook);
+        result.map_err(|kind|interpreter.error(kind,span))?;
+        let data=self.execute_operation(operation,interpreter)?.clone();
+        let mut hook=interpreter.hook.take().unwrap();
+        let result=hook.operation_done(operation,&data,interpreter);
+        interpreter.hook=Some(hook);
+        result.map_err(|kind|interpreter.error(kind,span))?;
+        interpreter.result=data;
+        return Ok(&interpreter.result);
+    }
+    fn execute_operation<'int>(&self,operation:&Operation<'doc>,interpreter:&'int mut Interpreter<'doc>)->RunResult<'doc,&'int Data<'doc>> {
+        let span=operation.span;
+        use OperationKind::*;
+        match &operation.kind {
+            Add(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.arithmetic("+",b));
+            },
+            Sub(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.arithmetic("-",b));
+            },
+            Mul(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.arithmetic("*",b));
+            },
+            Div(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.arithmetic("/",b));
+            },
+            Mod(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.arithmetic("//",b));
+            },
+            Assign(var,value)=>{
+                let data=interpreter.resolve(&value.data);
+                return interpreter.assign(var,data,span);
+            },
+            Equal(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare("==",b));
+            },
+            NotEqual(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare("!=",b));
+            },
+            Greater(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare(">",b));
+            },
+            Less(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare("<",b));
+            },
+            GreaterEqual(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare(">=",b));
+            },
+            LessEqual(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.compare("<=",b));
+            },
+            And(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.and(b));
+            },
+            Or(vars)=>{
+                return interpreter.apply(vars,span,|a,b|a.or(b));
+            },
+            Not(var)=>{
+                return interpreter.apply(&[*var,*var],span,|a,_|a.not());
+            },
+            Print(var)=>{
+                interpreter.scope().entry(var).or_insert(Default::default());
+                let data=&interpreter.scopes.last().unwrap()[var];
+                if let Err(kind)=interpreter.streams.print(data) {
+                    return Err(interpreter.error(kind,Some(span)));
+                }
+            },
+            Input(var,kind)=>{
+                let data=match interpreter.streams.read(*kind) {
+                    Ok(data)=>data,
+                    Err(kind)=>return Err(interpreter.error(kind,Some(span))),
+                };
+                return interpreter.assign(var,data,span);
+            },
+            Get(var,list,index)=>{
+                let index=interpreter.resolve(&index.data);
+                let data=interpreter.scope().entry(list).or_insert(Default::default()).get(&index);
+                let data=data.map_err(|kind|interpreter.error(kind,Some(span)))?;
+                return interpreter.assign(var,data,span);
+            },
+            Set(list,index,value)=>{
+                let index=interpreter.resolve(&index.data);
+                let value=interpreter.resolve(&value.data);
+                return interpreter.update(list,span,|list|list.size_at(&index),|list|list.set(&index,value));
+            },
+            Push(list,value)=>{
+                let value=interpreter.resolve(&value.data);
+                let added=interpreter.measure_item(&value);
+                return interpreter.resize(list,span,added,0,|list|list.push(value));
+            },
+            Pop(list,var)=>{
+                let data=interpreter.scope().entry(list).or_insert(Default::default()).pop();
+                let data=data.map_err(|kind|interpreter.error(kind,Some(span)))?;
+                interpreter.grow(0,interpreter.measure_item(&data));
+                return interpreter.assign(var,data,span);
+            },
+            Remove(list,index)=>{
+                let index=interpreter.resolve(&index.data);
+                let removed=interpreter.measure(list,|list|list.size_at(&index));
+                return interpreter.resize(list,span,0,removed,|list|list.remove(&index));
+            },
+            Contains(var,map,key)=>{
+                let key=interpreter.resolve(&key.data);
+                let found=interpreter.scope().entry(map).or_insert(Default::default()).contains(&key);
+                let found=found.map_err(|kind|interpreter.error(kind,Some(span)))?;
+                return interpreter.assign(var,Data::Bool(found),span);
+            },
+            Keys(var,map)=>{
+                let keys=interpreter.scope().entry(map).or_insert(Default::default()).keys();
+                let keys=keys.map_err(|kind|interpreter.error(kind,Some(span)))?;
+                return interpreter.assign(var,keys,span);
+            },
+            Length(var,list)=>{
+                let length=interpreter.scope().entry(list).or_insert(Default::default()).length();
+                let length=length.map_err(|kind|interpreter.error(kind,Some(span)))?;
+                return interpreter.assign(var,Data::Int(length as i64),span);
+            },
+            Call(call)=>{
+                self.run_function(call,Some(span),interpreter)?;
+            },
+            AssignCall(var,call)=>{
+                let data=self.run_function(call,Some(span),interpreter)?;
+                return interpreter.assign(var,data,span);
+            },
+            Return(value)=>{
+                let data=interpreter.resolve(&value.data);
+                interpreter.flow=Some(Flow::Return(data));
+            },
+            Conditional{to_compare,inner,otherwise}=>{
+                let taken=*self.run_operation(to_compare,interpreter)?==Data::Bool(true);
+                if interpreter.flow.is_some() {
+                    return Ok(&Data::None);
+                }
+                if taken {
+                    self.run_block(inner,interpreter)?;
+                } else if let Some(otherwise)=otherwise {
+                    self.run_block(otherwise,interpreter)?;
+                }
+            },
+            Loop{condition,body}=>{
+                loop {
+                    let taken=*self.run_operation(condition,interpreter)?==Data::Bool(true);
+                    if !taken||interpreter.flow.is_some() {
+                        break;
+                    }
+                    self.run_block(body,interpreter)?;
+                    match interpreter.flow {
+                        Some(Flow::Break(_))=>{
+                            interpreter.flow=None;
+                            break;
+                        },
+                        Some(Flow::Continue(_))=>interpreter.flow=None,
+                        Some(Flow::Return(_))=>break,
+                        None=>{},
+                    }
+                }
+            },
+            Break=>{
+                interpreter.flow=Some(Flow::Break(span));
+            },
+            Continue=>{
+                interpreter.flow=Some(Flow::Continue(span));
+            },
+        }
+        return Ok(&Data::None);
+    }
+    /// Run a single operation in the top-level scope of `interpreter` and return its value.
+    pub fn evaluate(&self,operation:&Operation<'doc>,interpreter:&mut Interpreter<'doc>)->RunResult<'doc,Data<'doc>> {
+        let result=self.run_operation(operation,interpreter).map(|data|data.clone());
+        let result=interpreter.finish(result);
+        return match (result,interpreter.flow.take()) {
+            (Err(e),_)=>Err(e),
+            (Ok(_),Some(Flow::Return(data)))=>Ok(data),
+            (Ok(_),Some(Flow::Break(span)))=>Err(interpreter.error(RuntimeErrorKind::OutsideLoop("@>"),Some(span))),
+            (Ok(_),Some(Flow::Continue(span)))=>Err(interpreter.error(RuntimeErrorKind::OutsideLoop("@<"),Some(span))),
+            (Ok(data),None)=>Ok(data),
+        };
+    }
+    /// Call a function from the top level and return what it returned.
+    pub fn call(&self,call:&FunctionCall<'doc>,interpreter:&mut Interpreter<'doc>)->RunResult<'doc,Data<'doc>> {
+        let result=self.run_function(call,None,interpreter);
+        return interpreter.finish(result);
+    }
+    /// Run the top-level statements with a fresh [`Interpreter`].
+    pub fn run(&self)->RunResult<'doc,()> {
+        return self.run_with(&mut Interpreter::new());
+    }
+    /// Run the top-level statements, keeping all state in `interpreter`.
+    pub fn run_with(&self,interpreter:&mut Interpreter<'doc>)->RunResult<'doc,()> {
+        let result=self.statements.iter()
+            .try_for_each(|call|self.run_function(call,None,interpreter).map(|_|()));
+        return interpreter.finish(result);
+    }
+}
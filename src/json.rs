@@ -0,0 +1,269 @@
+use std::fmt::{
+    Display,
+    Formatter,
+    Result as FmtResult,
+};
+use crate::data::Data;
+
+
+/// A JSON value, just enough for the machine-readable output formats.
+#[derive(Clone,Debug,PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Number(f64),
+    Str(String),
+    Array(Vec<Json>),
+    /// Keeps the order the fields were added in.
+    Object(Vec<(String,Json)>),
+}
+impl Json {
+    /// An object from `(key,value)` pairs.
+    pub(crate) fn object<const N:usize>(fields:[(&str,Json);N])->Self {
+        Json::Object(fields.into_iter().map(|(key,value)|(key.to_string(),value)).collect())
+    }
+    /// Read a JSON document, `None` if it isn't valid JSON.
+    pub(crate) fn parse(text:&str)->Option<Self> {
+        let mut reader=Reader{text,at:0};
+        let json=reader.value()?;
+        reader.whitespace();
+        if reader.at<text.len() {
+            return None;
+        }
+        return Some(json);
+    }
+    /// The field `key` of an object.
+    pub(crate) fn get(&self,key:&str)->Option<&Json> {
+        match self {
+            Json::Object(fields)=>fields.iter().find(|(k,_)|k==key).map(|(_,value)|value),
+            _=>None,
+        }
+    }
+    pub(crate) fn as_str(&self)->Option<&str> {
+        match self {
+            Json::Str(s)=>Some(s),
+            _=>None,
+        }
+    }
+    pub(crate) fn as_usize(&self)->Option<usize> {
+        match self {
+            Json::Int(n)=>usize::try_from(*n).ok(),
+            _=>None,
+        }
+    }
+}
+impl Display for Json {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        use Json::*;
+        match self {
+            Null=>write!(f,"null"),
+            Bool(b)=>write!(f,"{}",b),
+            Int(n)=>write!(f,"{}",n),
+            Number(n) if n.is_finite()=>write!(f,"{}",n),
+            Number(_)=>write!(f,"null"),
+            Str(s)=>write_str(f,s),
+            Array(items)=>{
+                write!(f,"[")?;
+                for (i,item) in items.iter().enumerate() {
+                    if i>0 {
+                        write!(f,",")?;
+                    }
+                    write!(f,"{}",item)?;
+                }
+                write!(f,"]")
+            },
+            Object(fields)=>{
+                write!(f,"{{")?;
+                for (i,(key,value)) in fields.iter().enumerate() {
+                    if i>0 {
+                        write!(f,",")?;
+                    }
+                    write_str(f,key)?;
+                    write!(f,":{}",value)?;
+                }
+                write!(f,"}}")
+            },
+        }
+    }
+}
+impl From<&str> for Json {
+    fn from(s:&str)->Self {Json::Str(s.to_string())}
+}
+impl From<String> for Json {
+    fn from(s:String)->Self {Json::Str(s)}
+}
+impl From<usize> for Json {
+    fn from(n:usize)->Self {Json::Int(n as i64)}
+}
+impl From<u32> for Json {
+    fn from(n:u32)->Self {Json::Int(n as i64)}
+}
+impl From<bool> for Json {
+    fn from(b:bool)->Self {Json::Bool(b)}
+}
+impl<'doc> From<&Data<'doc>> for Json {
+    fn from(data:&Data<'doc>)->Self {
+        match data {
+            Data::Var(name)=>Json::object([("var",(*name).into())]),
+            Data::Int(n)=>Json::Int(*n),
+            Data::Number(n)=>Json::Number(*n),
+            Data::Str(s)=>Json::Str(s.clone()),
+            Data::Bool(b)=>Json::Bool(*b),
+            Data::List(items)=>Json::Array(items.iter().map(Json::from).collect()),
+            Data::Map(entries)=>Json::Object(entries.iter().map(|(key,value)|(key.to_string(),value.into())).collect()),
+            Data::None=>Json::Null,
+        }
+    }
+}
+
+
+fn write_str(f:&mut Formatter,s:&str)->FmtResult {
+    write!(f,"\"")?;
+    for c in s.chars() {
+        match c {
+            '"'=>write!(f,"\\\"")?,
+            '\\'=>write!(f,"\\\\")?,
+            '\n'=>write!(f,"\\n")?,
+            '\r'=>write!(f,"\\r")?,
+            '\t'=>write!(f,"\\t")?,
+            c if (c as u32)<0x20=>write!(f,"\\u{:04x}",c as u32)?,
+            c=>write!(f,"{}",c)?,
+        }
+    }
+    write!(f,"\"")
+}
+
+
+/// Reads a JSON document one value at a time.
+struct Reader<'a> {
+    text:&'a str,
+    /// The byte offset of the next character.
+    at:usize,
+}
+impl<'a> Reader<'a> {
+    fn rest(&self)->&'a str {
+        &self.text[self.at..]
+    }
+    fn whitespace(&mut self) {
+        let rest=self.rest();
+        self.at+=rest.len()-rest.trim_start_matches([' ','\t','\r','\n']).len();
+    }
+    fn then(&mut self,expected:&str)->bool {
+        if self.rest().starts_with(expected) {
+            self.at+=expected.len();
+            return true;
+        }
+        return false;
+    }
+    fn value(&mut self)->Option<Json> {
+        self.whitespace();
+        if self.then("null") {
+            return Some(Json::Null);
+        } else if self.then("true") {
+            return Some(Json::Bool(true));
+        } else if self.then("false") {
+            return Some(Json::Bool(false));
+        } else if self.then("\"") {
+            return self.string().map(Json::Str);
+        } else if self.then("[") {
+            let mut items=Vec::new();
+            self.whitespace();
+            if self.then("]") {
+                return Some(Json::Array(items));
+            }
+            loop {
+                items.push(self.value()?);
+                self.whitespace();
+                if self.then("]") {
+                    return Some(Json::Array(items));
+                }
+                if !self.then(",") {
+                    return None;
+                }
+            }
+        } else if self.then("{") {
+            let mut fields=Vec::new();
+            self.whitespace();
+            if self.then("}") {
+                return Some(Json::Object(fields));
+            }
+            loop {
+                self.whitespace();
+                if !self.then("\"") {
+                    return None;
+                }
+                let key=self.string()?;
+                self.whitespace();
+                if !self.then(":") {
+                    return None;
+                }
+                fields.push((key,self.value()?));
+                self.whitespace();
+                if self.then("}") {
+                    return Some(Json::Object(fields));
+                }
+                if !self.then(",") {
+                    return None;
+                }
+            }
+        }
+        return self.number();
+    }
+    /// The rest of a string after its opening quote.
+    fn string(&mut self)->Option<String> {
+        let mut s=String::new();
+        loop {
+            let c=self.rest().chars().next()?;
+            self.at+=c.len_utf8();
+            match c {
+                '"'=>return Some(s),
+                '\\'=>{
+                    let escape=self.rest().chars().next()?;
+                    self.at+=escape.len_utf8();
+                    match escape {
+                        '"'|'\\'|'/'=>s.push(escape),
+                        'b'=>s.push('\u{8}'),
+                        'f'=>s.push('\u{c}'),
+                        'n'=>s.push('\n'),
+                        'r'=>s.push('\r'),
+                        't'=>s.push('\t'),
+                        'u'=>{
+                            let unit=self.hex()?;
+                            // Characters outside the BMP are written as a surrogate pair.
+                            let c=if (0xd800..0xdc00).contains(&unit)&&self.then("\\u") {
+                                let low=self.hex()?;
+                                char::from_u32(0x10000+((unit-0xd800)<<10)+(low.checked_sub(0xdc00)?))
+                            } else {
+                                char::from_u32(unit)
+                            };
+                            s.push(c.unwrap_or(char::REPLACEMENT_CHARACTER));
+                        },
+                        _=>return None,
+                    }
+                },
+                c=>s.push(c),
+            }
+        }
+    }
+    /// Four hex digits, like in `\u00e9`.
+    fn hex(&mut self)->Option<u32> {
+        let digits=self.rest().get(..4)?;
+        let unit=u32::from_str_radix(digits,16).ok()?;
+        self.at+=4;
+        return Some(unit);
+    }
+    fn number(&mut self)->Option<Json> {
+        let rest=self.rest();
+        let length=rest.find(|c:char|!(c.is_ascii_digit()||"+-.eE".contains(c))).unwrap_or(rest.len());
+        let number=&rest[..length];
+        if number.is_empty() {
+            return None;
+        }
+        self.at+=length;
+        if let Ok(n)=number.parse::<i64>() {
+            return Some(Json::Int(n));
+        }
+        return number.parse::<f64>().ok().map(Json::Number);
+    }
+}
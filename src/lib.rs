@@ -0,0 +1,96 @@
+use generic_parser::GenericParser;
+
+
+mod parser;
+mod ast;
+mod data;
+mod interpreter;
+mod error;
+mod span;
+mod bytecode;
+mod compiler;
+mod vm;
+mod repl;
+mod debugger;
+mod trace;
+mod profile;
+mod json;
+mod streams;
+mod generator;
+mod lsp;
+mod format;
+mod suggest;
+mod link;
+
+
+pub use parser::{
+    Parser,
+    ParseError,
+    ErrorKind,
+};
+pub use ast::{
+    Program,
+    Class,
+    Function,
+    FunctionCall,
+    InputKind,
+    Operation,
+    OperationKind,
+    Value,
+};
+pub use data::{
+    Data,
+    Key,
+};
+pub use interpreter::{
+    Interpreter,
+    Hook,
+    Limits,
+};
+pub use error::{
+    RuntimeError,
+    RuntimeErrorKind,
+    Frame,
+    Diagnostic,
+};
+pub use span::{
+    Span,
+    Source,
+};
+pub use bytecode::{
+    Bytecode,
+    CompiledFunction,
+    Instruction,
+    BinaryOp,
+};
+pub use vm::Vm;
+pub use debugger::{
+    Debugger,
+    Breakpoint,
+};
+pub use trace::{
+    Tracer,
+    TraceFormat,
+};
+pub use profile::{
+    Profiler,
+    Profile,
+    FunctionProfile,
+    OperationProfile,
+};
+pub use generator::Generator;
+pub use lsp::LanguageServer;
+pub use repl::{
+    Repl,
+    ReplError,
+};
+
+
+pub type Result<'doc,T>=std::result::Result<T,ParseError<'doc>>;
+
+
+/// Parse `source` into a [`Program`], or report every error in it. `filename` is only used when
+/// reporting errors. Calls aren't checked yet, [`Program::link`] does that.
+pub fn parse<'doc>(source:&'doc str,filename:&'doc str)->std::result::Result<Program<'doc>,Vec<Diagnostic<'doc>>> {
+    return GenericParser::new(source,filename).program(&Source::new(source,filename));
+}
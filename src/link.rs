@@ -0,0 +1,71 @@
+use std::slice;
+use crate::{
+    ast::*,
+    error::{
+        Diagnostic,
+        RuntimeErrorKind,
+    },
+    parser::ErrorKind,
+    suggest::similar_function,
+};
+
+
+impl<'doc> Program<'doc> {
+    /// Check that every call in a function and every top-level statement goes to a class and
+    /// function the program defines, with an argument for every parameter. Returns an error for
+    /// every call that doesn't, in the order they are in the source, so a typo is found before the
+    /// program runs.
+    pub fn link(&self)->Vec<Diagnostic<'doc>> {
+        let mut found=Vec::new();
+        for class in self.classes.values() {
+            for function in class.functions.values() {
+                calls(&function.operations,&mut found);
+            }
+        }
+        found.extend(self.statements.iter());
+        let mut errors:Vec<_>=found.into_iter().filter_map(|call|self.resolve(call)).collect();
+        errors.sort_by_key(|error|error.span.offset);
+        return errors;
+    }
+    /// The error for `call` if it goes nowhere or has the wrong number of arguments.
+    fn resolve(&self,call:&FunctionCall<'doc>)->Option<Diagnostic<'doc>> {
+        let kind=match self.classes.get(&call.class).map(|class|class.functions.get(call.function)) {
+            Some(Some(function)) if function.parameters.len()==call.arguments.len()=>return None,
+            Some(Some(function))=>RuntimeErrorKind::ArgumentCount {
+                class:call.class,
+                function:call.function.to_string(),
+                expected:function.parameters.len(),
+                found:call.arguments.len(),
+            },
+            Some(None)=>RuntimeErrorKind::UnknownFunction {
+                class:call.class,
+                function:call.function.to_string(),
+                suggestion:similar_function(&self.classes,call.class,call.function),
+            },
+            None=>RuntimeErrorKind::UnknownClass(call.class),
+        };
+        return Some(Diagnostic{kind:ErrorKind::Link(kind),span:call.span});
+    }
+}
+
+
+/// Every call in `operations`, including the ones nested in conditionals and loops.
+fn calls<'a,'doc>(operations:&'a [Operation<'doc>],found:&mut Vec<&'a FunctionCall<'doc>>) {
+    for operation in operations {
+        match &operation.kind {
+            OperationKind::Call(call)|OperationKind::AssignCall(_,call)=>found.push(call),
+            OperationKind::Conditional{to_compare,inner,otherwise}=>{
+                calls(slice::from_ref(&**to_compare),found);
+                calls(inner,found);
+                if let Some(otherwise)=otherwise {
+                    calls(otherwise,found);
+                }
+            },
+            OperationKind::Loop{condition,body}=>{
+                calls(slice::from_ref(&**condition),found);
+                calls(body,found);
+            },
+            _=>{},
+        }
+    }
+}
@@ -0,0 +1,464 @@
+use generic_parser::GenericParser;
+use std::{
+    collections::HashMap,
+    io::{
+        self,
+        BufRead,
+        Write,
+    },
+    ops::Range,
+};
+use crate::{
+    json::Json,
+    parser::Parser,
+    span::Source,
+};
+
+
+const PARSE_ERROR:i64=-32700;
+const METHOD_NOT_FOUND:i64=-32601;
+const SEVERITY_ERROR:usize=1;
+const SYMBOL_CLASS:usize=5;
+const SYMBOL_FUNCTION:usize=12;
+const COMPLETION_FUNCTION:usize=3;
+
+
+/// A function of an open file. Offsets are into the last version of the file that parsed.
+#[derive(Clone,Debug)]
+struct FunctionSymbol {
+    class:u32,
+    name:String,
+    parameters:Vec<String>,
+    offset:usize,
+    text:String,
+}
+impl FunctionSymbol {
+    fn signature(&self)->String {
+        if self.parameters.len()==0 {
+            return format!("{}>{}",self.class,self.name);
+        }
+        return format!("{}>{}({})",self.class,self.name,self.parameters.join(","));
+    }
+}
+
+
+/// What the server knows about an open file.
+#[derive(Debug,Default)]
+struct Document {
+    text:String,
+    /// The classes by number with the offset of that number.
+    classes:Vec<(u32,usize)>,
+    /// The functions of the last version that parsed, so completion keeps working while a file
+    /// is half edited.
+    functions:Vec<FunctionSymbol>,
+}
+impl Document {
+    fn function(&self,class:u32,name:&str)->Option<&FunctionSymbol> {
+        return self.functions.iter().find(|function|function.class==class&&function.name==name);
+    }
+    /// The call like `0>A` at `offset`, including right after its last letter.
+    fn call_at(&self,offset:usize)->Option<(u32,&str)> {
+        let line_start=self.text[..offset].rfind('\n').map_or(0,|i|i+1);
+        let line_end=self.text[offset..].find('\n').map_or(self.text.len(),|i|offset+i);
+        return calls(&self.text[line_start..line_end])
+            .into_iter()
+            .find(|(range,..)|range.start+line_start<=offset&&offset<=range.end+line_start)
+            .map(|(_,class,name)|(class,name));
+    }
+    /// The function being called or defined at `offset`.
+    fn function_at(&self,offset:usize)->Option<&FunctionSymbol> {
+        if let Some((class,name))=self.call_at(offset) {
+            return self.function(class,name);
+        }
+        return self.functions.iter().find(|function|function.offset<=offset&&offset<=function.offset+function.name.len());
+    }
+}
+
+
+/// A Language Server Protocol server for `.happy` files. It reports parse errors and calls to
+/// functions that don't exist, finds the functions calls go to, shows the source of a function
+/// when hovering a call, lists the classes and functions of a file and completes function names.
+///
+/// Files are synced whole on every change. Positions are converted between byte offsets and the
+/// UTF-16 columns the protocol uses.
+#[derive(Debug,Default)]
+pub struct LanguageServer {
+    documents:HashMap<String,Document>,
+    shut_down:bool,
+}
+impl LanguageServer {
+    pub fn new()->Self {
+        Self::default()
+    }
+    /// Answer JSON-RPC messages from `input` on `output` until the client sends `exit` or the
+    /// input ends. Returns whether the client asked the server to shut down first, which decides
+    /// the exit code.
+    pub fn run<R:BufRead,W:Write>(&mut self,mut input:R,mut output:W)->io::Result<bool> {
+        while let Some(body)=read_message(&mut input)? {
+            let message=match Json::parse(&body) {
+                Some(message)=>message,
+                None=>{
+                    write_message(&mut output,&error_response(Json::Null,PARSE_ERROR,"The message is not valid JSON"))?;
+                    continue;
+                },
+            };
+            let method=message.get("method").and_then(Json::as_str).unwrap_or("");
+            if method=="exit" {
+                return Ok(self.shut_down);
+            }
+            let params=message.get("params").cloned().unwrap_or(Json::Null);
+            let result=self.handle(method,&params,&mut output)?;
+            // Only requests have an id and get an answer, notifications don't.
+            if let Some(id)=message.get("id").cloned() {
+                let response=match result {
+                    Some(result)=>Json::object([("jsonrpc","2.0".into()),("id",id),("result",result)]),
+                    None=>error_response(id,METHOD_NOT_FOUND,&format!("Unknown method `{}`",method)),
+                };
+                write_message(&mut output,&response)?;
+            }
+        }
+        return Ok(false);
+    }
+    /// The result of a request, or `None` for methods the server doesn't know.
+    fn handle<W:Write>(&mut self,method:&str,params:&Json,output:&mut W)->io::Result<Option<Json>> {
+        let uri=params.get("textDocument")
+            .and_then(|document|document.get("uri"))
+            .and_then(Json::as_str)
+            .unwrap_or("")
+            .to_string();
+        let result=match method {
+            "initialize"=>capabilities(),
+            "initialized"=>Json::Null,
+            "shutdown"=>{
+                self.shut_down=true;
+                Json::Null
+            },
+            "textDocument/didOpen"=>{
+                let text=params.get("textDocument").and_then(|document|document.get("text")).and_then(Json::as_str);
+                self.update(&uri,text.unwrap_or("").to_string(),output)?;
+                Json::Null
+            },
+            "textDocument/didChange"=>{
+                let text=match params.get("contentChanges") {
+                    Some(Json::Array(changes))=>changes.last().and_then(|change|change.get("text")).and_then(Json::as_str),
+                    _=>None,
+                };
+                if let Some(text)=text {
+                    self.update(&uri,text.to_string(),output)?;
+                }
+                Json::Null
+            },
+            "textDocument/didClose"=>{
+                self.documents.remove(&uri);
+                publish(output,&uri,Vec::new())?;
+                Json::Null
+            },
+            "textDocument/definition"=>self.definition(&uri,params),
+            "textDocument/hover"=>self.hover(&uri,params),
+            "textDocument/documentSymbol"=>self.symbols(&uri),
+            "textDocument/completion"=>self.completion(&uri,params),
+            _=>return Ok(None),
+        };
+        return Ok(Some(result));
+    }
+    /// Parse a new version of a file and publish its diagnostics.
+    fn update<W:Write>(&mut self,uri:&str,text:String,output:&mut W)->io::Result<()> {
+        let document=self.documents.entry(uri.to_string()).or_default();
+        let source=Source::new(&text,uri);
+        let mut diagnostics=Vec::new();
+        let errors=match GenericParser::new(&text,uri).program(&source) {
+            Ok(program)=>{
+                let mut classes:Vec<_>=program.classes.iter().map(|(number,class)|(*number,class.span.offset)).collect();
+                classes.sort();
+                let mut functions=Vec::new();
+                for (class,c) in program.classes.iter() {
+                    for (name,function) in c.functions.iter() {
+                        functions.push(FunctionSymbol {
+                            class:*class,
+                            name:name.to_string(),
+                            parameters:function.parameters.iter().map(|name|name.to_string()).collect(),
+                            offset:function.span.offset,
+                            text:function.text.to_string(),
+                        });
+                    }
+                }
+                functions.sort_by_key(|function|function.offset);
+                document.classes=classes;
+                document.functions=functions;
+                program.link()
+            },
+            Err(errors)=>errors,
+        };
+        for e in errors {
+            let start=e.span.offset;
+            let end=match text[start..].chars().next() {
+                Some(c) if c!='\n'=>start+c.len_utf8(),
+                _=>start,
+            };
+            diagnostics.push(Json::object([
+                ("range",range(&text,start..end)),
+                ("severity",SEVERITY_ERROR.into()),
+                ("source","happy".into()),
+                ("code",e.kind.code().into()),
+                ("message",format!("{}\n{}",e.kind,e.kind.help()).into()),
+            ]));
+        }
+        document.text=text;
+        return publish(output,uri,diagnostics);
+    }
+    /// The document and byte offset a request points at.
+    fn position(&self,uri:&str,params:&Json)->Option<(&Document,usize)> {
+        let document=self.documents.get(uri)?;
+        let position=params.get("position")?;
+        let line=position.get("line")?.as_usize()?;
+        let character=position.get("character")?.as_usize()?;
+        return Some((document,offset(&document.text,line,character)));
+    }
+    fn definition(&self,uri:&str,params:&Json)->Json {
+        let (document,offset)=match self.position(uri,params) {
+            Some(position)=>position,
+            None=>return Json::Null,
+        };
+        let (class,name)=match document.call_at(offset) {
+            Some(call)=>call,
+            None=>return Json::Null,
+        };
+        // A call to a missing function still knows which class it meant.
+        let target=match document.function(class,name) {
+            Some(function)=>function.offset..function.offset+function.name.len(),
+            None=>match document.classes.iter().find(|(number,_)|*number==class) {
+                Some((number,offset))=>*offset..*offset+number.to_string().len(),
+                None=>return Json::Null,
+            },
+        };
+        return Json::object([
+            ("uri",uri.into()),
+            ("range",range(&document.text,target)),
+        ]);
+    }
+    fn hover(&self,uri:&str,params:&Json)->Json {
+        let function=match self.position(uri,params) {
+            Some((document,offset))=>document.function_at(offset),
+            None=>None,
+        };
+        return match function {
+            Some(function)=>Json::object([
+                ("contents",Json::object([
+                    ("kind","markdown".into()),
+                    ("value",format!("```happy\n{}:\n    {}\n```",function.class,function.text).into()),
+                ])),
+            ]),
+            None=>Json::Null,
+        };
+    }
+    fn symbols(&self,uri:&str)->Json {
+        let document=match self.documents.get(uri) {
+            Some(document)=>document,
+            None=>return Json::Array(Vec::new()),
+        };
+        let text=&document.text;
+        let symbols=document.classes.iter()
+            .map(|(class,offset)|{
+                let number=*offset..*offset+class.to_string().len();
+                let functions:Vec<_>=document.functions.iter().filter(|function|function.class==*class).collect();
+                let end=functions.iter().map(|function|function.offset+function.text.len()).max().unwrap_or(number.end);
+                let children=functions.iter()
+                    .map(|function|Json::object([
+                        ("name",function.name.as_str().into()),
+                        ("detail",function.signature().into()),
+                        ("kind",SYMBOL_FUNCTION.into()),
+                        ("range",range(text,function.offset..function.offset+function.text.len())),
+                        ("selectionRange",range(text,function.offset..function.offset+function.name.len())),
+                    ]))
+                    .collect();
+                Json::object([
+                    ("name",class.to_string().into()),
+                    ("kind",SYMBOL_CLASS.into()),
+                    ("range",range(text,*offset..end)),
+                    ("selectionRange",range(text,number)),
+                    ("children",Json::Array(children)),
+                ])
+            })
+            .collect();
+        return Json::Array(symbols);
+    }
+    /// Every function, or only the functions of the class when the cursor is after `0>`.
+    fn completion(&self,uri:&str,params:&Json)->Json {
+        let (document,offset)=match self.position(uri,params) {
+            Some(position)=>position,
+            None=>return Json::Array(Vec::new()),
+        };
+        let line_start=document.text[..offset].rfind('\n').map_or(0,|i|i+1);
+        let before=document.text[line_start..offset].trim_end_matches(|c:char|c.is_ascii_uppercase()).trim_end();
+        let class=before.strip_suffix('>').and_then(|before|{
+            let before=before.trim_end();
+            let digits=before.len()-before.trim_end_matches(|c:char|c.is_ascii_digit()).len();
+            before[before.len()-digits..].parse::<u32>().ok()
+        });
+        let items=document.functions.iter()
+            .filter(|function|class.iter().all(|class|function.class==*class))
+            .map(|function|{
+                let label=match class {
+                    Some(_)=>function.name.clone(),
+                    None=>format!("{}>{}",function.class,function.name),
+                };
+                Json::object([
+                    ("label",label.into()),
+                    ("kind",COMPLETION_FUNCTION.into()),
+                    ("detail",function.signature().into()),
+                ])
+            })
+            .collect();
+        return Json::Array(items);
+    }
+}
+
+
+fn capabilities()->Json {
+    Json::object([
+        ("capabilities",Json::object([
+            // The whole file is sent on every change.
+            ("textDocumentSync",1usize.into()),
+            ("definitionProvider",true.into()),
+            ("hoverProvider",true.into()),
+            ("documentSymbolProvider",true.into()),
+            ("completionProvider",Json::object([
+                ("triggerCharacters",Json::Array(vec![">".into()])),
+            ])),
+        ])),
+        ("serverInfo",Json::object([
+            ("name","happy".into()),
+            ("version",env!("CARGO_PKG_VERSION").into()),
+        ])),
+    ])
+}
+fn publish<W:Write>(output:&mut W,uri:&str,diagnostics:Vec<Json>)->io::Result<()> {
+    let notification=Json::object([
+        ("jsonrpc","2.0".into()),
+        ("method","textDocument/publishDiagnostics".into()),
+        ("params",Json::object([
+            ("uri",uri.into()),
+            ("diagnostics",Json::Array(diagnostics)),
+        ])),
+    ]);
+    return write_message(output,&notification);
+}
+fn error_response(id:Json,code:i64,message:&str)->Json {
+    Json::object([
+        ("jsonrpc","2.0".into()),
+        ("id",id),
+        ("error",Json::object([
+            ("code",Json::Int(code)),
+            ("message",message.into()),
+        ])),
+    ])
+}
+/// Read the body of the next message, `None` once the input ends.
+fn read_message<R:BufRead>(input:&mut R)->io::Result<Option<String>> {
+    let mut length=None;
+    loop {
+        let mut header=String::new();
+        if input.read_line(&mut header)?==0 {
+            return Ok(None);
+        }
+        let header=header.trim_end();
+        if header.is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name,value))=header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length=value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+    let mut body=vec![0;length.unwrap_or(0)];
+    input.read_exact(&mut body)?;
+    return String::from_utf8(body)
+        .map(Some)
+        .map_err(|e|io::Error::new(io::ErrorKind::InvalidData,e));
+}
+fn write_message<W:Write>(output:&mut W,message:&Json)->io::Result<()> {
+    let body=message.to_string();
+    write!(output,"Content-Length: {}\r\n\r\n{}",body.len(),body)?;
+    return output.flush();
+}
+/// Every call like `0>A` in `line`, with the bytes it covers.
+fn calls(line:&str)->Vec<(Range<usize>,u32,&str)> {
+    let bytes=line.as_bytes();
+    let skip_spaces=|mut i:usize|{
+        while i<bytes.len()&&(bytes[i]==b' '||bytes[i]==b'\t') {
+            i+=1;
+        }
+        i
+    };
+    let mut calls=Vec::new();
+    let mut i=0;
+    while i<bytes.len() {
+        if !bytes[i].is_ascii_digit() {
+            i+=1;
+            continue;
+        }
+        let start=i;
+        while i<bytes.len()&&bytes[i].is_ascii_digit() {
+            i+=1;
+        }
+        let class=&line[start..i];
+        let mut end=skip_spaces(i);
+        if end>=bytes.len()||bytes[end]!=b'>' {
+            continue;
+        }
+        end=skip_spaces(end+1);
+        let name_start=end;
+        while end<bytes.len()&&bytes[end].is_ascii_uppercase() {
+            end+=1;
+        }
+        if end>name_start {
+            if let Ok(class)=class.parse() {
+                calls.push((start..end,class,&line[name_start..end]));
+            }
+            i=end;
+        }
+    }
+    return calls;
+}
+/// The byte offset of a 0-based line and UTF-16 column, clamped to the text.
+fn offset(text:&str,line:usize,character:usize)->usize {
+    let mut start=0;
+    for _ in 0..line {
+        match text[start..].find('\n') {
+            Some(i)=>start+=i+1,
+            None=>return text.len(),
+        }
+    }
+    let mut units=0;
+    for (i,c) in text[start..].char_indices() {
+        if units>=character||c=='\n' {
+            return start+i;
+        }
+        units+=c.len_utf16();
+    }
+    return text.len();
+}
+/// The protocol position of a byte offset, which may be from an older version of the text.
+fn position(text:&str,offset:usize)->Json {
+    let mut offset=offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset-=1;
+    }
+    let before=&text[..offset];
+    let line_start=before.rfind('\n').map_or(0,|i|i+1);
+    let character:usize=before[line_start..].chars().map(char::len_utf16).sum();
+    return Json::object([
+        ("line",before.matches('\n').count().into()),
+        ("character",character.into()),
+    ]);
+}
+fn range(text:&str,range:Range<usize>)->Json {
+    Json::object([
+        ("start",position(text,range.start)),
+        ("end",position(text,range.end)),
+    ])
+}
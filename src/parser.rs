@@ -0,0 +1,745 @@
+use generic_parser::{
+    EOFError,
+    Error,
+    GenericParser,
+};
+use std::{
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult,
+    },
+    num::{
+        ParseIntError,
+        ParseFloatError,
+    },
+    collections::{
+        HashMap,
+        BTreeMap,
+    },
+};
+use crate::{
+    ast::*,
+    data::{
+        Data,
+        Key,
+    },
+    error::{
+        Diagnostic,
+        RuntimeErrorKind,
+    },
+    span::{
+        Source,
+        Span,
+    },
+    Result,
+};
+
+
+pub trait Parser<'doc> {
+    /// Parse a whole program. Parsing carries on after most errors to report all of them.
+    fn program(self,source:&Source<'doc>)->std::result::Result<Program<'doc>,Vec<Diagnostic<'doc>>>;
+    fn class(&mut self,source:&Source<'doc>)->Result<'doc,(u32,Class<'doc>)>;
+    fn function(&mut self,source:&Source<'doc>)->Result<'doc,(&'doc str,Function<'doc>)>;
+    /// Operations separated by `,` up to `end`, which is left for the caller.
+    fn block(&mut self,source:&Source<'doc>,end:&str)->Result<'doc,Vec<Operation<'doc>>>;
+    fn operation(&mut self,source:&Source<'doc>)->Result<'doc,Operation<'doc>>;
+    fn operation_kind(&mut self,source:&Source<'doc>)->Result<'doc,OperationKind<'doc>>;
+    fn call(&mut self,source:&Source<'doc>)->Result<'doc,FunctionCall<'doc>>;
+    fn var_name(&mut self,source:&Source<'doc>)->Result<'doc,&'doc str>;
+    /// [`Parser::data`] with where it starts.
+    fn value(&mut self,source:&Source<'doc>)->Result<'doc,Value<'doc>>;
+    fn data(&mut self,source:&Source<'doc>)->Result<'doc,Data<'doc>>;
+}
+impl<'doc> Parser<'doc> for GenericParser<'doc,ErrorKind> {
+    fn program(mut self,source:&Source<'doc>)->std::result::Result<Program<'doc>,Vec<Diagnostic<'doc>>> {
+        let mut classes=HashMap::new();
+        let mut statements=Vec::new();
+        while !self.skip(WHITESPACE).is_eof() {
+            let mut sp=self.subparser();
+            match sp.class(source) {
+                Ok((n,c))=>{
+                    sp.finish();
+                    if classes.contains_key(&n) {
+                        source.report(Diagnostic{kind:ErrorKind::ClassExists(n),span:c.span});
+                    } else {
+                        classes.insert(n,c);
+                    }
+                },
+                Err(e)=>{
+                    sp.finish_error();
+                    if e.important {
+                        source.recover(e);
+                        next_declaration(&mut self);
+                        continue;
+                    }
+                    match self.call(source) {
+                        Ok(call)=>statements.push(call),
+                        Err(e)=>{
+                            source.recover(e);
+                            next_declaration(&mut self);
+                        },
+                    }
+                },
+            }
+        }
+        let errors=source.take_errors();
+        if errors.len()>0 {
+            return Err(errors);
+        }
+        return Ok(Program {
+            statements,
+            classes,
+        });
+    }
+    fn class(&mut self,source:&Source<'doc>)->Result<'doc,(u32,Class<'doc>)> {
+        let name=self.while_any(NUMBERS);
+        if name.len()<1 {
+            return Err(error(self,source,ErrorKind::ExpectedClassName,false));
+        }
+        let span=source.span(name);
+        if !self.skip(WHITESPACE).then(":")? {
+            return Err(error(self,source,ErrorKind::ExpectedColon,false));
+        }
+        let name=match name.parse::<u32>() {
+            Ok(n)=>n,
+            Err(e)=>return Err(error(self,source,e.into(),true)),
+        };
+        let mut functions=HashMap::new();
+        while !self.skip(WHITESPACE).then(";")? {
+            let (name,function)=match self.function(source) {
+                Ok(function)=>function,
+                Err(e) if e.kind==ErrorKind::UnexpectedEOF=>return Err(e),
+                Err(e)=>{
+                    // Carry on with the next function.
+                    source.recover(e);
+                    synchronize(self,&[";"]);
+                    self.then(";")?;
+                    continue;
+                },
+            };
+            if functions.contains_key(name) {
+                source.report(Diagnostic{kind:ErrorKind::FunctionExists(name.to_string()),span:function.span});
+                continue;
+            }
+            functions.insert(name,function);
+        }
+        return Ok((name,Class{functions,span}));
+    }
+    fn function(&mut self,source:&Source<'doc>)->Result<'doc,(&'doc str,Function<'doc>)> {
+        let name=self.while_any(UPPER_LETTERS);
+        if name.len()<1 {
+            return Err(error(self,source,ErrorKind::ExpectedFunctionName,false));
+        }
+        let span=source.span(name);
+        let mut parameters=Vec::new();
+        if self.skip(WHITESPACE).then("(")? {
+            while !self.skip(WHITESPACE).then(")")? {
+                let parameter=self.var_name(source)?;
+                if parameters.contains(&parameter) {
+                    source.report(Diagnostic{kind:ErrorKind::ParameterExists(parameter.to_string()),span:source.span(parameter)});
+                } else {
+                    parameters.push(parameter);
+                }
+                if !self.skip(WHITESPACE).then(",")? {
+                    if !self.skip(WHITESPACE).then(")")? {
+                        return Err(error(self,source,ErrorKind::ExpectedParenthesisEnd,true));
+                    }
+                    break;
+                }
+            }
+        }
+        if !self.skip(WHITESPACE).then(":")? {
+            return Err(error(self,source,ErrorKind::ExpectedColon,true));
+        }
+        let operations=self.block(source,";")?;
+        if !self.skip(WHITESPACE).then(";")? {
+            return Err(error(self,source,ErrorKind::ExpectedSemiColon,true));
+        }
+        let text=source.between(name,here(self));
+        return Ok((name,Function{parameters,operations,span,text}));
+    }
+    fn block(&mut self,source:&Source<'doc>,end:&str)->Result<'doc,Vec<Operation<'doc>>> {
+        let mut operations=Vec::new();
+        while !self.skip(WHITESPACE).test(end)? {
+            match self.operation(source) {
+                Ok(operation)=>operations.push(operation),
+                Err(e) if e.kind==ErrorKind::UnexpectedEOF=>return Err(e),
+                Err(e)=>{
+                    // Carry on after the next `,`, or leave the end of the block to the caller.
+                    source.recover(e);
+                    synchronize(self,&[",",";","}"]);
+                },
+            }
+            if !self.skip(WHITESPACE).then(",")? {
+                break;
+            }
+        }
+        return Ok(operations);
+    }
+    fn operation(&mut self,source:&Source<'doc>)->Result<'doc,Operation<'doc>> {
+        let start=here(self);
+        let span=source.span(start);
+        let kind=self.operation_kind(source)?;
+        let text=source.between(start,here(self)).trim_end();
+        return Ok(Operation{kind,span,text});
+    }
+    fn operation_kind(&mut self,source:&Source<'doc>)->Result<'doc,OperationKind<'doc>> {
+        if self.then("(")? {
+            let to_compare=Box::new(self.skip(WHITESPACE).operation(source)?);
+            if !self.skip(WHITESPACE).then(")")? {
+                return Err(error(self,source,ErrorKind::ExpectedParenthesisEnd,true));
+            }
+            if self.skip(WHITESPACE).then("@")? {
+                if !self.skip(WHITESPACE).then("{")? {
+                    return Err(error(self,source,ErrorKind::ExpectedLoopBlock,true));
+                }
+                let body=self.block(source,"}")?;
+                if !self.skip(WHITESPACE).then("}")? {
+                    return Err(error(self,source,ErrorKind::ExpectedLoopBlockEnd,true));
+                }
+                return Ok(OperationKind::Loop{condition:to_compare,body});
+            }
+            if !self.skip(WHITESPACE).then("?")? {
+                return Err(error(self,source,ErrorKind::ExpectedConditionalBlock,true));
+            }
+            if !self.skip(WHITESPACE).then("{")? {
+                return Err(error(self,source,ErrorKind::ExpectedConditionalBlock,true));
+            }
+            let inner=self.block(source,"}")?;
+            if !self.skip(WHITESPACE).then("}")? {
+                return Err(error(self,source,ErrorKind::ExpectedConditionalBlockEnd,true));
+            }
+            if !self.skip(WHITESPACE).then(":")? {
+                return Ok(OperationKind::Conditional{to_compare,inner,otherwise:None});
+            }
+            if !self.skip(WHITESPACE).then("{")? {
+                return Err(error(self,source,ErrorKind::ExpectedConditionalOtherwiseBlock,true));
+            }
+            let otherwise=self.block(source,"}")?;
+            if !self.skip(WHITESPACE).then("}")? {
+                return Err(error(self,source,ErrorKind::ExpectedConditionalOtherwiseBlockEnd,true));
+            }
+            let otherwise=Some(otherwise);
+            return Ok(OperationKind::Conditional{to_compare,inner,otherwise});
+        } else if self.then("^")? {
+            if self.skip(WHITESPACE).test_any(&[",",";","}"])? {
+                let span=source.span(here(self));
+                return Ok(OperationKind::Return(Value{data:Data::None,span}));
+            }
+            return Ok(OperationKind::Return(self.value(source)?));
+        } else if self.then("@")? {
+            if self.then(">")? {
+                return Ok(OperationKind::Break);
+            } else if self.then("<")? {
+                return Ok(OperationKind::Continue);
+            }
+            return Err(error(self,source,ErrorKind::ExpectedLoopControl,true));
+        } else if let Ok(name)=self.var_name(source) {
+            self.skip(WHITESPACE);
+            if self.test_any(&[",",";","}"])? {
+                return Ok(OperationKind::Print(name));
+            } else if self.then("[")? {
+                let index=self.skip(WHITESPACE).value(source)?;
+                if !self.skip(WHITESPACE).then("]")? {
+                    return Err(error(self,source,ErrorKind::ExpectedIndexEnd,true));
+                }
+                if !self.skip(WHITESPACE).then("=")? {
+                    return Err(error(self,source,ErrorKind::ExpectedAssign,true));
+                }
+                let value=self.skip(WHITESPACE).value(source)?;
+                return Ok(OperationKind::Set(name,index,value));
+            } else if self.then("<<")? {
+                let value=self.skip(WHITESPACE).value(source)?;
+                return Ok(OperationKind::Push(name,value));
+            } else if self.then("~")? {
+                let index=self.skip(WHITESPACE).value(source)?;
+                return Ok(OperationKind::Remove(name,index));
+            } else if self.then(">>")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Pop(name,other));
+            } else if self.then("==")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Equal([name,other]));
+            } else if self.then(">=")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::GreaterEqual([name,other]));
+            } else if self.then("<=")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::LessEqual([name,other]));
+            } else if self.then(">")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Greater([name,other]));
+            } else if self.then("<")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Less([name,other]));
+            } else if self.then("!=")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::NotEqual([name,other]));
+            } else if self.then("!")? {
+                return Ok(OperationKind::Not(name));
+            } else if self.then("$$")? {
+                return Ok(OperationKind::Input(name,InputKind::All));
+            } else if self.then("$#")? {
+                return Ok(OperationKind::Input(name,InputKind::Number));
+            } else if self.then("$?")? {
+                return Ok(OperationKind::Input(name,InputKind::Eof));
+            } else if self.then("$")? {
+                return Ok(OperationKind::Input(name,InputKind::Line));
+            } else if self.then("|")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Or([name,other]));
+            } else if self.then("&")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::And([name,other]));
+            } else if self.then("=")? {
+                if at_call(self.skip(WHITESPACE))? {
+                    return Ok(OperationKind::AssignCall(name,self.call(source)?));
+                }
+                if self.then("#")? {
+                    let list=self.skip(WHITESPACE).var_name(source)?;
+                    return Ok(OperationKind::Length(name,list));
+                }
+                let other=self.value(source)?;
+                if let Data::Var(list)=other.data {
+                    if self.skip(WHITESPACE).then("[")? {
+                        if self.skip(WHITESPACE).then("]")? {
+                            return Ok(OperationKind::Keys(name,list));
+                        }
+                        let index=self.value(source)?;
+                        if !self.skip(WHITESPACE).then("]")? {
+                            return Err(error(self,source,ErrorKind::ExpectedIndexEnd,true));
+                        }
+                        if self.skip(WHITESPACE).then("?")? {
+                            return Ok(OperationKind::Contains(name,list,index));
+                        }
+                        return Ok(OperationKind::Get(name,list,index));
+                    }
+                }
+                return Ok(OperationKind::Assign(name,other));
+            } else if self.then("+")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Add([name,other]));
+            } else if self.then("-")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Sub([name,other]));
+            } else if self.then("*")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Mul([name,other]));
+            } else if self.then("//")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Mod([name,other]));
+            } else if self.then("/")? {
+                let other=self.skip(WHITESPACE).var_name(source)?;
+                return Ok(OperationKind::Div([name,other]));
+            }
+        } else if self.test_any(NUMBERS)? {
+            return Ok(OperationKind::Call(self.call(source)?));
+        }
+        return Err(error(self,source,ErrorKind::ExpectedOperation,true));
+    }
+    fn call(&mut self,source:&Source<'doc>)->Result<'doc,FunctionCall<'doc>> {
+        let start=here(self);
+        let span=source.span(start);
+        let class=self.while_any(NUMBERS);
+        if class.len()<1 {
+            return Err(error(self,source,ErrorKind::ExpectedCall,false));
+        }
+        let class=match class.parse::<u32>() {
+            Ok(n)=>n,
+            Err(e)=>return Err(error(self,source,e.into(),true)),
+        };
+        if !self.skip(WHITESPACE).then(">")? {
+            return Err(error(self,source,ErrorKind::ExpectedCall,true));
+        }
+        let function=self.skip(WHITESPACE).while_any(UPPER_LETTERS);
+        if function.len()<1 {
+            return Err(error(self,source,ErrorKind::ExpectedFunctionName,true));
+        }
+        let mut arguments=Vec::new();
+        if !self.skip(WHITESPACE).is_eof()&&self.then("(")? {
+            while !self.skip(WHITESPACE).then(")")? {
+                arguments.push(self.value(source)?);
+                if !self.skip(WHITESPACE).then(",")? {
+                    if !self.skip(WHITESPACE).then(")")? {
+                        return Err(error(self,source,ErrorKind::ExpectedParenthesisEnd,true));
+                    }
+                    break;
+                }
+            }
+        }
+        let text=source.between(start,here(self)).trim_end();
+        return Ok(FunctionCall{class,function,arguments,span,text});
+    }
+    fn var_name(&mut self,source:&Source<'doc>)->Result<'doc,&'doc str> {
+        let name=self.while_any(LETTERS);
+        if name.len()<1 {
+            return Err(error(self,source,ErrorKind::ExpectedVariableName,true));
+        }
+        return Ok(name);
+    }
+    fn value(&mut self,source:&Source<'doc>)->Result<'doc,Value<'doc>> {
+        let span=source.span(here(self));
+        let data=self.data(source)?;
+        return Ok(Value{data,span});
+    }
+    fn data(&mut self,source:&Source<'doc>)->Result<'doc,Data<'doc>> {
+        if self.then("\"")? {
+            let mut s=String::new();
+            'main:loop {
+                s.push_str(self.until_any(&["\"","\\"]));
+                if self.then("\"")? {
+                    break 'main;
+                } else if self.then("\\")? {
+                    if self.then("n")? {
+                        s.push('\n');
+                    } else if self.then("t")? {
+                        s.push('\t');
+                    } else if self.then("r")? {
+                        s.push('\r');
+                    } else if self.then("x")? {
+                        let digits=self.eat(2)?;
+                        if !digits.chars().all(|c|c.is_ascii_hexdigit()) {
+                            return Err(error(self,source,ErrorKind::InvalidAsciiEscape,true));
+                        }
+                        match u8::from_str_radix(digits,16) {
+                            Ok(n)=>s.push(n as char),
+                            Err(e)=>return Err(error(self,source,e.into(),true)),
+                        }
+                    } else if self.then("u")? {
+                        if !self.then("{")? {
+                            return Err(error(self,source,ErrorKind::InvalidUnicodeEscape,true));
+                        }
+                        let digits=self.until_counted("}",6);
+                        if !digits.chars().all(|c|c.is_ascii_hexdigit()) {
+                            return Err(error(self,source,ErrorKind::InvalidUnicodeEscape,true));
+                        }
+                        match u32::from_str_radix(digits,16) {
+                            Ok(n)=>match char::from_u32(n) {
+                                Some(c)=>s.push(c),
+                                None=>return Err(error(self,source,ErrorKind::InvalidUnicodeEscape,true)),
+                            },
+                            Err(e)=>return Err(error(self,source,e.into(),true)),
+                        }
+                    } else if self.then("0")? {
+                        s.push('\0');
+                    } else if self.then("\"")? {
+                        s.push('"');
+                    } else if self.then("\\")? {
+                        s.push('\\');
+                    }
+                }
+            }
+            return Ok(Data::Str(s.to_string()));
+        } else if self.then("[")? {
+            let mut items=Vec::new();
+            while !self.skip(WHITESPACE).then("]")? {
+                items.push(self.data(source)?);
+                if !self.skip(WHITESPACE).then(",")? {
+                    if !self.skip(WHITESPACE).then("]")? {
+                        return Err(error(self,source,ErrorKind::ExpectedListEnd,true));
+                    }
+                    break;
+                }
+            }
+            return Ok(Data::List(items));
+        } else if self.then("{")? {
+            let mut entries=BTreeMap::new();
+            while !self.skip(WHITESPACE).then("}")? {
+                let key=match Key::new(&self.data(source)?) {
+                    Some(key)=>key,
+                    None=>return Err(error(self,source,ErrorKind::ExpectedMapKey,true)),
+                };
+                if !self.skip(WHITESPACE).then(":")? {
+                    return Err(error(self,source,ErrorKind::ExpectedColon,true));
+                }
+                entries.insert(key,self.skip(WHITESPACE).data(source)?);
+                if !self.skip(WHITESPACE).then(",")? {
+                    if !self.skip(WHITESPACE).then("}")? {
+                        return Err(error(self,source,ErrorKind::ExpectedMapEnd,true));
+                    }
+                    break;
+                }
+            }
+            return Ok(Data::Map(entries));
+        } else if self.then("true")? {
+            return Ok(Data::Bool(true));
+        } else if self.then("false")? {
+            return Ok(Data::Bool(false));
+        } else if let Ok(name)=self.var_name(source) {
+            return Ok(Data::Var(name));
+        } else {
+            let negative=self.then("-")?;
+            let mut num=self.while_any(NUMBERS).to_string();
+            let float=self.then(".")?;
+            if float {
+                num.push('.');
+                num.push_str(self.while_any(NUMBERS));
+            }
+            if num.len()<1 {
+                return Err(error(self,source,ErrorKind::ExpectedNumber,true));
+            }
+            if negative {
+                num.insert(0,'-');
+            }
+            if float {
+                return match num.parse::<f64>() {
+                    Ok(n)=>Ok(Data::Number(n)),
+                    Err(e)=>Err(error(self,source,e.into(),true)),
+                };
+            }
+            return match num.parse::<i64>() {
+                Ok(n)=>Ok(Data::Int(n)),
+                Err(e)=>Err(error(self,source,e.into(),true)),
+            };
+        }
+    }
+}
+
+/// Create an error at the cursor.
+pub(crate) fn error<'doc>(parser:&mut GenericParser<'doc,ErrorKind>,source:&Source<'doc>,kind:ErrorKind,important:bool)->ParseError<'doc> {
+    let span=source.span(here(parser));
+    return ParseError{kind,important,span:Some(span)};
+}
+/// An empty slice at the cursor, which [`Source::span`] turns into a position.
+fn here<'doc>(parser:&mut GenericParser<'doc,ErrorKind>)->&'doc str {
+    return parser.while_any(&[]);
+}
+/// Skip to the next of `stops` that isn't in brackets or a string, to carry on parsing after an
+/// error. The stop is left at the cursor.
+fn synchronize<'doc>(parser:&mut GenericParser<'doc,ErrorKind>,stops:&[&str]) {
+    let mut depth=0usize;
+    loop {
+        parser.until_any(&["\"","(","[","{",")","]","}",",",";"]);
+        if parser.is_eof() {
+            return;
+        }
+        if depth==0&&stops.iter().any(|stop|parser.test(stop).unwrap_or(false)) {
+            return;
+        }
+        if parser.then("\"").unwrap_or(false) {
+            loop {
+                parser.until_any(&["\"","\\"]);
+                if parser.then("\\").unwrap_or(false) {
+                    let _=parser.then("\"").unwrap_or(false)||parser.then("\\").unwrap_or(false);
+                } else if !parser.then("\"").unwrap_or(false) {
+                    return;
+                } else {
+                    break;
+                }
+            }
+        } else {
+            if parser.test_any(&["(","[","{"]).unwrap_or(false) {
+                depth+=1;
+            } else if parser.test_any(&[")","]","}"]).unwrap_or(false) {
+                depth=depth.saturating_sub(1);
+            }
+            let _=parser.eat(1);
+        }
+    }
+}
+/// Skip to the next line that starts with a number, where the next class or top-level statement
+/// most likely is, to carry on parsing after an error outside of a class.
+fn next_declaration<'doc>(parser:&mut GenericParser<'doc,ErrorKind>) {
+    loop {
+        parser.until_any(&["\n"]);
+        if !parser.then("\n").unwrap_or(false)||parser.test_any(NUMBERS).unwrap_or(false) {
+            return;
+        }
+    }
+}
+/// Whether a call like `0>A` starts at the cursor. Doesn't move the cursor.
+fn at_call<'doc>(parser:&mut GenericParser<'doc,ErrorKind>)->Result<'doc,bool> {
+    let mut sp=parser.subparser();
+    let found=sp.while_any(NUMBERS).len()>0&&sp.skip(WHITESPACE).then(">")?;
+    sp.finish_error();
+    return Ok(found);
+}
+
+
+/// An error found while parsing, with where it was found.
+#[derive(Debug,PartialEq)]
+pub struct ParseError<'doc> {
+    pub kind:ErrorKind,
+    /// Whether the text can't be anything else, so no other way to parse it is tried.
+    pub important:bool,
+    /// `None` for errors about the input ending early, which point just past its end.
+    pub span:Option<Span<'doc>>,
+}
+impl<'doc> From<Error<'doc,ErrorKind>> for ParseError<'doc> {
+    /// `GenericParser` only creates errors itself when the input ends early, every other error
+    /// comes from [`error`] with where it was found.
+    fn from(error:Error<'doc,ErrorKind>)->Self {
+        ParseError{kind:ErrorKind::UnexpectedEOF,important:error.important,span:None}
+    }
+}
+
+
+#[derive(Debug,PartialEq)]
+pub enum ErrorKind {
+    UnexpectedEOF,
+    ExpectedClassName,
+    ExpectedFunctionName,
+    ExpectedVariableName,
+    ExpectedOperation,
+    ExpectedColon,
+    ExpectedSemiColon,
+    ExpectedParenthesisEnd,
+    ExpectedConditionalBlock,
+    ExpectedConditionalBlockEnd,
+    ExpectedConditionalOtherwiseBlock,
+    ExpectedConditionalOtherwiseBlockEnd,
+    ExpectedLoopBlock,
+    ExpectedLoopBlockEnd,
+    ExpectedLoopControl,
+    ExpectedNumber,
+    ExpectedListEnd,
+    ExpectedMapEnd,
+    ExpectedMapKey,
+    ExpectedIndexEnd,
+    ExpectedAssign,
+    ExpectedCall,
+    InvalidAsciiEscape,
+    InvalidUnicodeEscape,
+    NumberParseError(String),
+    FunctionExists(String),
+    ParameterExists(String),
+    ClassExists(u32),
+    /// A call [`Program::link`] found going nowhere, the same error running it would give.
+    Link(RuntimeErrorKind),
+}
+impl From<ParseIntError> for ErrorKind {
+    fn from(o:ParseIntError)->Self {
+        Self::NumberParseError(o.to_string())
+    }
+}
+impl From<ParseFloatError> for ErrorKind {
+    fn from(o:ParseFloatError)->Self {
+        Self::NumberParseError(o.to_string())
+    }
+}
+impl EOFError for ErrorKind {
+    fn create_eof()->Self {Self::UnexpectedEOF}
+}
+impl ErrorKind {
+    /// The code of the error, like `H0012`. Codes never change, so they can be searched for.
+    pub fn code(&self)->&'static str {
+        use ErrorKind::*;
+        match self {
+            UnexpectedEOF=>"H0001",
+            ExpectedClassName=>"H0002",
+            ExpectedFunctionName=>"H0003",
+            ExpectedVariableName=>"H0004",
+            ExpectedOperation=>"H0005",
+            ExpectedColon=>"H0006",
+            ExpectedSemiColon=>"H0007",
+            ExpectedParenthesisEnd=>"H0008",
+            ExpectedConditionalBlock=>"H0009",
+            ExpectedConditionalBlockEnd=>"H0010",
+            ExpectedConditionalOtherwiseBlock=>"H0011",
+            ExpectedConditionalOtherwiseBlockEnd=>"H0012",
+            ExpectedLoopBlock=>"H0013",
+            ExpectedLoopBlockEnd=>"H0014",
+            ExpectedLoopControl=>"H0015",
+            ExpectedNumber=>"H0016",
+            ExpectedListEnd=>"H0017",
+            ExpectedMapEnd=>"H0018",
+            ExpectedMapKey=>"H0019",
+            ExpectedIndexEnd=>"H0020",
+            ExpectedAssign=>"H0021",
+            ExpectedCall=>"H0022",
+            InvalidAsciiEscape=>"H0023",
+            InvalidUnicodeEscape=>"H0024",
+            NumberParseError(_)=>"H0025",
+            FunctionExists(_)=>"H0026",
+            ParameterExists(_)=>"H0027",
+            ClassExists(_)=>"H0028",
+            Link(kind)=>kind.code(),
+        }
+    }
+    /// A note on how to fix the error.
+    pub fn help(&self)->String {
+        use ErrorKind::*;
+        let help=match self {
+            UnexpectedEOF=>"a class, function, block, string or list is missing its end",
+            ExpectedClassName=>"classes start with their number, like `0:`",
+            ExpectedFunctionName=>"function names must be UPPERCASE letters, like `MAIN`",
+            ExpectedVariableName=>"variable names must be lowercase letters, like `count`",
+            ExpectedOperation=>"operations look like `a=1`, `a+b`, `a`, `^a` or `0>MAIN`, see grammar.txt",
+            ExpectedColon=>"class numbers, function names and map keys are followed by `:`",
+            ExpectedSemiColon=>"operations are separated by `,`, and functions and classes end with `;`",
+            ExpectedParenthesisEnd=>"parameters and arguments are separated by `,` and end with `)`",
+            ExpectedConditionalBlock=>"a conditional looks like `(a==b)?{...}` and a loop like `(a<b)@{...}`",
+            ExpectedConditionalBlockEnd|ExpectedConditionalOtherwiseBlockEnd|ExpectedLoopBlockEnd=>{
+                "operations in a block are separated by `,` and the block ends with `}`"
+            },
+            ExpectedConditionalOtherwiseBlock=>"the otherwise block of a conditional looks like `(a==b)?{...}:{...}`",
+            ExpectedLoopBlock=>"a loop looks like `(a<b)@{...}`",
+            ExpectedLoopControl=>"`@>` leaves a loop and `@<` goes on with its next round",
+            ExpectedNumber=>"values are numbers, strings, `true`, `false`, lists, maps or variables",
+            ExpectedListEnd=>"list items are separated by `,`, like `[1,2,3]`",
+            ExpectedMapEnd=>"map entries are separated by `,`, like `{\"a\":1,2:3}`",
+            ExpectedMapKey=>"map keys must be numbers or strings",
+            ExpectedIndexEnd=>"indexing looks like `a=list[0]` or `list[0]=a`",
+            ExpectedAssign=>"setting an item looks like `list[0]=a`",
+            ExpectedCall=>"calls are the class number, `>` and the function name, like `0>MAIN` or `0>ADD(a,1)`",
+            InvalidAsciiEscape=>"`\\x` is followed by two hex digits, like `\\x41`",
+            InvalidUnicodeEscape=>"`\\u` is followed by up to six hex digits in braces, like `\\u{1F600}`",
+            NumberParseError(_)=>"numbers must fit in 64 bits and class numbers in 32 bits",
+            FunctionExists(_)=>"rename or remove one of the functions",
+            ParameterExists(_)=>"every parameter of a function needs its own name",
+            ClassExists(_)=>"give one of the classes another number",
+            Link(kind)=>return kind.help().unwrap_or_default(),
+        };
+        return help.to_string();
+    }
+}
+impl Display for ErrorKind {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        use ErrorKind::*;
+        match self {
+            UnexpectedEOF=>write!(f,"The program ended early"),
+            ExpectedClassName=>write!(f,"Expected a class number"),
+            ExpectedFunctionName=>write!(f,"Expected a function name"),
+            ExpectedVariableName=>write!(f,"Expected a variable name"),
+            ExpectedOperation=>write!(f,"Expected an operation"),
+            ExpectedColon=>write!(f,"Expected `:`"),
+            ExpectedSemiColon=>write!(f,"Expected `,` or `;`"),
+            ExpectedParenthesisEnd=>write!(f,"Expected `)`"),
+            ExpectedConditionalBlock=>write!(f,"Expected `?{{` or `@{{` after the condition"),
+            ExpectedConditionalBlockEnd=>write!(f,"Expected `}}` to end the conditional"),
+            ExpectedConditionalOtherwiseBlock=>write!(f,"Expected `{{` to start the otherwise block"),
+            ExpectedConditionalOtherwiseBlockEnd=>write!(f,"Expected `}}` to end the otherwise block"),
+            ExpectedLoopBlock=>write!(f,"Expected `{{` to start the loop"),
+            ExpectedLoopBlockEnd=>write!(f,"Expected `}}` to end the loop"),
+            ExpectedLoopControl=>write!(f,"Expected `@>` or `@<`"),
+            ExpectedNumber=>write!(f,"Expected a value"),
+            ExpectedListEnd=>write!(f,"Expected `]` to end the list"),
+            ExpectedMapEnd=>write!(f,"Expected `}}` to end the map"),
+            ExpectedMapKey=>write!(f,"Expected a map key"),
+            ExpectedIndexEnd=>write!(f,"Expected `]` to end the index"),
+            ExpectedAssign=>write!(f,"Expected `=` after the index"),
+            ExpectedCall=>write!(f,"Expected a call"),
+            InvalidAsciiEscape=>write!(f,"Invalid `\\x` escape"),
+            InvalidUnicodeEscape=>write!(f,"Invalid `\\u` escape"),
+            NumberParseError(e)=>write!(f,"Invalid number: {}",e),
+            FunctionExists(name)=>write!(f,"The function `{}` is defined twice",name),
+            ParameterExists(name)=>write!(f,"The parameter `{}` is listed twice",name),
+            ClassExists(class)=>write!(f,"The class `{}` is defined twice",class),
+            Link(kind)=>write!(f,"{}",kind),
+        }
+    }
+}
+
+pub(crate) const WHITESPACE:&[&str]=&[
+    " ","\t","\r","\n",
+];
+const NUMBERS:&[&str]=&[
+    "1","2","3","4","5","6","7","8","9","0",
+];
+const LETTERS:&[&str]=&[
+    "a","b","c","d","e","f","g","h","i","j","k","l","m","n","o","p","q","r","s","t","u","v","w","x","y","z",
+];
+const UPPER_LETTERS:&[&str]=&[
+    "A","B","C","D","E","F","G","H","I","J","K","L","M","N","O","P","Q","R","S","T","U","V","W","X","Y","Z",
+];
@@ -0,0 +1,166 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::{
+        Display,
+        Formatter,
+        Result as FmtResult,
+    },
+    rc::Rc,
+    time::{
+        Duration,
+        Instant,
+    },
+};
+use crate::{
+    ast::Operation,
+    error::RuntimeErrorKind,
+    interpreter::{
+        Hook,
+        Interpreter,
+    },
+    span::Span,
+};
+
+
+/// How many of the hottest operations a [`Profile`] shows.
+const HOTTEST:usize=10;
+
+
+/// What a function did while the program ran.
+#[derive(Clone,Debug,Default,PartialEq)]
+pub struct FunctionProfile {
+    pub calls:usize,
+    /// Operations run by the function and everything it called.
+    pub inclusive_operations:usize,
+    /// Operations run by the function itself.
+    pub exclusive_operations:usize,
+    pub inclusive_time:Duration,
+    pub exclusive_time:Duration,
+}
+
+
+#[derive(Clone,Debug,PartialEq)]
+pub struct OperationProfile<'doc> {
+    pub name:&'static str,
+    pub span:Span<'doc>,
+    pub count:usize,
+}
+
+
+/// The numbers collected by a [`Profiler`]. Its `Display` is a report sorted by inclusive time.
+#[derive(Clone,Debug,Default)]
+pub struct Profile<'doc> {
+    pub functions:HashMap<(u32,&'doc str),FunctionProfile>,
+    /// Every operation that ran by where it is in the source.
+    pub operations:HashMap<(&'doc str,usize),OperationProfile<'doc>>,
+}
+impl<'doc> Display for Profile<'doc> {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        let mut functions:Vec<_>=self.functions.iter().collect();
+        functions.sort_by(|(a,a_profile),(b,b_profile)|b_profile.inclusive_time.cmp(&a_profile.inclusive_time).then(a.cmp(b)));
+        writeln!(f,"{:<16} {:>8} {:>12} {:>12} {:>14} {:>14}","function","calls","ops (incl)","ops (excl)","time (incl)","time (excl)")?;
+        for ((class,function),profile) in functions {
+            writeln!(
+                f,
+                "{:<16} {:>8} {:>12} {:>12} {:>14.3?} {:>14.3?}",
+                format!("{}>{}",class,function),
+                profile.calls,
+                profile.inclusive_operations,
+                profile.exclusive_operations,
+                profile.inclusive_time,
+                profile.exclusive_time,
+            )?;
+        }
+        let mut operations:Vec<_>=self.operations.values().collect();
+        operations.sort_by(|a,b|b.count.cmp(&a.count).then(a.span.file.cmp(b.span.file)).then(a.span.offset.cmp(&b.span.offset)));
+        writeln!(f)?;
+        writeln!(f,"{:<16} {:>8}  {}","operation","count","location")?;
+        for operation in operations.into_iter().take(HOTTEST) {
+            let span=operation.span;
+            writeln!(f,"{:<16} {:>8}  {}:{}:{}",operation.name,operation.count,span.file,span.line,span.column)?;
+        }
+        return Ok(());
+    }
+}
+
+
+/// A function that is running, with what it had done when it started.
+#[derive(Debug)]
+struct Call<'doc> {
+    function:(u32,&'doc str),
+    started:Instant,
+    operations:usize,
+    /// Time spent in the functions it called.
+    children:Duration,
+}
+
+
+#[derive(Debug,Default)]
+struct State<'doc> {
+    profile:Profile<'doc>,
+    calls:Vec<Call<'doc>>,
+    operations:usize,
+}
+
+
+/// A [`Hook`] that counts calls and operations and times functions. Clones share their numbers,
+/// so hand a clone to [`Interpreter::with_hook`] and read the [`Profile`] from the original.
+#[derive(Clone,Debug,Default)]
+pub struct Profiler<'doc> {
+    state:Rc<RefCell<State<'doc>>>,
+}
+impl<'doc> Profiler<'doc> {
+    pub fn new()->Self {
+        Self::default()
+    }
+    pub fn profile(&self)->Profile<'doc> {
+        return self.state.borrow().profile.clone();
+    }
+}
+impl<'doc> Hook<'doc> for Profiler<'doc> {
+    fn operation(&mut self,operation:&Operation<'doc>,_interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        let mut state=self.state.borrow_mut();
+        let state=&mut *state;
+        state.operations+=1;
+        if let Some(call)=state.calls.last() {
+            state.profile.functions.entry(call.function).or_default().exclusive_operations+=1;
+        }
+        let span=operation.span;
+        state.profile.operations.entry((span.file,span.offset))
+            .or_insert(OperationProfile{name:operation.kind.name(),span,count:0})
+            .count+=1;
+        return Ok(());
+    }
+    fn enter(&mut self,interpreter:&mut Interpreter<'doc>) {
+        let frame=match interpreter.stack().last() {
+            Some(frame)=>frame,
+            None=>return,
+        };
+        let mut state=self.state.borrow_mut();
+        let function=(frame.class,frame.function);
+        state.profile.functions.entry(function).or_default().calls+=1;
+        let operations=state.operations;
+        state.calls.push(Call{function,started:Instant::now(),operations,children:Duration::ZERO});
+    }
+    fn exit(&mut self,_interpreter:&mut Interpreter<'doc>) {
+        let mut state=self.state.borrow_mut();
+        let call=match state.calls.pop() {
+            Some(call)=>call,
+            None=>return,
+        };
+        let elapsed=call.started.elapsed();
+        // A recursive call is already counted by the outermost call of the same function.
+        let recursive=state.calls.iter().any(|outer|outer.function==call.function);
+        let operations=state.operations-call.operations;
+        let profile=state.profile.functions.entry(call.function).or_default();
+        profile.exclusive_time+=elapsed.saturating_sub(call.children);
+        if !recursive {
+            profile.inclusive_time+=elapsed;
+            profile.inclusive_operations+=operations;
+        }
+        if let Some(caller)=state.calls.last_mut() {
+            caller.children+=elapsed;
+        }
+    }
+}
@@ -0,0 +1,184 @@
+use generic_parser::GenericParser;
+use std::{
+    cell::{
+        Cell,
+        RefCell,
+    },
+    collections::HashMap,
+};
+use crate::{
+    ast::*,
+    data::Data,
+    error::{
+        Diagnostic,
+        RuntimeError,
+    },
+    interpreter::Interpreter,
+    parser::{
+        error,
+        Parser,
+        ErrorKind,
+        WHITESPACE,
+    },
+    span::Source,
+    ParseError,
+};
+
+
+#[derive(Debug)]
+pub enum ReplError<'a> {
+    Parse(Vec<Diagnostic<'a>>),
+    Runtime(RuntimeError<'a>),
+}
+
+
+/// An interactive session. Every entry is either class definitions, which replace classes with
+/// the same number, or operations, which run in a top-level scope that lives as long as the
+/// session.
+///
+/// The session keeps the text of every entry in an [`Arena`] so the parsed program can borrow it.
+/// Values and errors only borrow the session, never the arena directly, so none outlive it.
+#[derive(Debug)]
+pub struct Repl {
+    program:RefCell<Program<'static>>,
+    interpreter:RefCell<Interpreter<'static>>,
+    /// The text of every entry by its file name.
+    sources:RefCell<HashMap<&'static str,&'static str>>,
+    last:Cell<&'static str>,
+    /// Declared last so it is dropped after everything borrowing from it.
+    arena:Arena,
+}
+impl Repl {
+    pub fn new()->Self {
+        Repl {
+            program:RefCell::new(Program {
+                classes:HashMap::new(),
+                statements:Vec::new(),
+            }),
+            interpreter:RefCell::new(Interpreter::new()),
+            sources:RefCell::new(HashMap::new()),
+            last:Cell::new(""),
+            arena:Arena::default(),
+        }
+    }
+    /// Whether `input` is a whole entry: every string, bracket and `:` block is closed.
+    pub fn is_complete(input:&str)->bool {
+        let mut depth=0i32;
+        let mut blocks=0i32;
+        let mut in_string=false;
+        let mut escaped=false;
+        let mut after_brace=false;
+        for c in input.chars() {
+            if in_string {
+                match c {
+                    _ if escaped=>escaped=false,
+                    '\\'=>escaped=true,
+                    '"'=>in_string=false,
+                    _=>{},
+                }
+                continue;
+            }
+            match c {
+                '"'=>in_string=true,
+                '('|'['|'{'=>depth+=1,
+                ')'|']'|'}'=>depth-=1,
+                // `}:{` starts the otherwise block of a conditional
+                ':' if depth==0&&!after_brace=>blocks+=1,
+                ';' if depth==0=>blocks-=1,
+                _=>{},
+            }
+            if !c.is_whitespace() {
+                after_brace=c=='}';
+            }
+        }
+        return !in_string&&depth<=0&&blocks<=0;
+    }
+    /// Parse and run an entry, returning the value of every operation or call that has one.
+    pub fn eval(&self,input:&str)->Result<Vec<Data<'_>>,ReplError<'_>> {
+        let mut sources=self.sources.borrow_mut();
+        let file=self.arena.add(format!("<repl:{}>",sources.len()+1));
+        let text=input.trim_end();
+        let text=if is_class(text)||text.ends_with(',')||text.ends_with(';') {
+            self.arena.add(text.to_string())
+        } else {
+            self.arena.add(format!("{}\n;",text))
+        };
+        sources.insert(file,text);
+        drop(sources);
+        self.last.set(text);
+        let source=Source::new(text,file);
+        let mut program=self.program.borrow_mut();
+        let mut interpreter=self.interpreter.borrow_mut();
+        let mut values=Vec::new();
+        if is_class(text) {
+            let entry=GenericParser::new(text,file).program(&source).map_err(ReplError::Parse)?;
+            program.classes.extend(entry.classes);
+            for call in entry.statements.iter() {
+                values.push(program.call(call,&mut interpreter).map_err(ReplError::Runtime)?);
+            }
+        } else {
+            let failed=|e:ParseError<'static>|ReplError::Parse(vec![source.diagnostic(e)]);
+            let mut parser=GenericParser::new(text,file);
+            let mut operations=Vec::new();
+            while !parser.skip(WHITESPACE).is_eof() {
+                operations.push(parser.operation(&source).map_err(failed)?);
+                if !parser.skip(WHITESPACE).then(",").map_err(|e|failed(e.into()))?&&!parser.then(";").map_err(|e|failed(e.into()))? {
+                    return Err(failed(error(&mut parser,&source,ErrorKind::ExpectedSemiColon,true)));
+                }
+            }
+            for operation in operations.iter() {
+                values.push(program.evaluate(operation,&mut interpreter).map_err(ReplError::Runtime)?);
+            }
+        }
+        values.retain(|data|*data!=Data::None);
+        return Ok(values);
+    }
+    /// Print an error from [`Repl::eval`] with the entry it points into.
+    pub fn print_error(&self,error:&ReplError,color:bool) {
+        match error {
+            ReplError::Parse(errors)=>for e in errors.iter() {
+                e.print_with_context(self.last.get(),color);
+            },
+            ReplError::Runtime(e)=>{
+                let source=e.span.and_then(|span|self.sources.borrow().get(span.file).copied()).unwrap_or(self.last.get());
+                e.print_with_context(source,color);
+            },
+        }
+    }
+}
+impl Default for Repl {
+    fn default()->Self {Self::new()}
+}
+
+
+/// The text of every entry. Texts are only ever added, each in an allocation of its own that
+/// doesn't move when more are added, and are all freed together when the arena is dropped.
+#[derive(Debug,Default)]
+struct Arena {
+    texts:RefCell<Vec<*mut str>>,
+}
+impl Arena {
+    /// Keep `text` until the arena is dropped.
+    fn add(&self,text:String)->&'static str {
+        let text=Box::into_raw(text.into_boxed_str());
+        self.texts.borrow_mut().push(text);
+        // SAFETY: the text is only freed when the arena is dropped. `Repl` drops its arena after
+        // the program and interpreter borrowing it, and only hands out borrows of itself.
+        return unsafe {&*text};
+    }
+}
+impl Drop for Arena {
+    fn drop(&mut self) {
+        for text in self.texts.get_mut().drain(..) {
+            // SAFETY: every text came from `Box::into_raw` and nothing borrows it anymore.
+            drop(unsafe {Box::from_raw(text)});
+        }
+    }
+}
+
+
+/// Whether an entry starts with a class definition like `0:`.
+fn is_class(text:&str)->bool {
+    let rest=text.trim_start().trim_start_matches(|c:char|c.is_ascii_digit());
+    return rest.len()<text.trim_start().len()&&rest.trim_start().starts_with(':');
+}
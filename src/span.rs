@@ -0,0 +1,81 @@
+use std::cell::RefCell;
+use crate::{
+    error::Diagnostic,
+    ParseError,
+};
+
+
+/// A position in a source file. `line` and `column` start at 1, `column` counts characters.
+#[derive(Clone,Copy,Debug,PartialEq,Eq)]
+pub struct Span<'doc> {
+    pub file:&'doc str,
+    pub offset:usize,
+    pub line:usize,
+    pub column:usize,
+}
+
+
+/// The text being parsed, used to turn the slices `GenericParser` hands out into [`Span`]s.
+#[derive(Debug)]
+pub struct Source<'doc> {
+    pub text:&'doc str,
+    pub file:&'doc str,
+    line_starts:Vec<usize>,
+    /// The errors the parser carried on after.
+    errors:RefCell<Vec<Diagnostic<'doc>>>,
+}
+impl<'doc> Source<'doc> {
+    pub fn new(text:&'doc str,file:&'doc str)->Self {
+        let mut line_starts=vec![0];
+        line_starts.extend(text.match_indices('\n').map(|(i,_)|i+1));
+        return Source{text,file,line_starts,errors:RefCell::new(Vec::new())};
+    }
+    /// `error` with where it was found.
+    pub fn diagnostic(&self,error:ParseError<'doc>)->Diagnostic<'doc> {
+        let span=self.error_span(&error);
+        return Diagnostic{kind:error.kind,span};
+    }
+    /// Remember `error` to report it once parsing is done.
+    pub(crate) fn recover(&self,error:ParseError<'doc>) {
+        let diagnostic=self.diagnostic(error);
+        self.report(diagnostic);
+    }
+    /// Remember an error the parser found without creating it, to report it once parsing is done.
+    pub(crate) fn report(&self,diagnostic:Diagnostic<'doc>) {
+        self.errors.borrow_mut().push(diagnostic);
+    }
+    /// The errors the parser carried on after, in the order it found them.
+    pub(crate) fn take_errors(&self)->Vec<Diagnostic<'doc>> {
+        return self.errors.take();
+    }
+    /// Where `error` was found. Errors about the input ending early point just past its end.
+    pub fn error_span(&self,error:&ParseError<'doc>)->Span<'doc> {
+        return error.span.unwrap_or_else(||self.span_at(self.text.len()));
+    }
+    /// Where `at` starts in the text. `at` has to be a slice of [`Source::text`].
+    pub fn span(&self,at:&'doc str)->Span<'doc> {
+        let offset=(at.as_ptr() as usize)
+            .saturating_sub(self.text.as_ptr() as usize)
+            .min(self.text.len());
+        return self.span_at(offset);
+    }
+    /// The text from `start` up to `end`, both slices of [`Source::text`].
+    pub(crate) fn between(&self,start:&'doc str,end:&'doc str)->&'doc str {
+        return &self.text[self.span(start).offset..self.span(end).offset];
+    }
+    /// The span for a byte offset into the text.
+    pub fn span_at(&self,offset:usize)->Span<'doc> {
+        let line=match self.line_starts.binary_search(&offset) {
+            Ok(i)=>i,
+            Err(i)=>i-1,
+        };
+        let line_start=self.line_starts[line];
+        let column=self.text.get(line_start..offset).map_or(0,|s|s.chars().count());
+        return Span {
+            file:self.file,
+            offset,
+            line:line+1,
+            column:column+1,
+        };
+    }
+}
@@ -0,0 +1,112 @@
+use std::{
+    fmt::{
+        Debug,
+        Formatter,
+        Result as FmtResult,
+    },
+    io::{
+        BufRead,
+        BufWriter,
+        Write,
+        stdin,
+        stdout,
+        stderr,
+    },
+};
+use crate::{
+    ast::InputKind,
+    data::Data,
+    error::RuntimeErrorKind,
+};
+
+
+fn io_error(e:std::io::Error)->RuntimeErrorKind {
+    RuntimeErrorKind::Io(e.to_string())
+}
+
+
+/// Where a running program reads its input from and writes its output to. By default these are
+/// the real streams, with the output buffered until the program is done or reads input.
+pub(crate) struct Streams<'doc> {
+    /// `None` reads from the real stdin, which is locked for every read so the debugger and the
+    /// REPL can read from it too.
+    stdin:Option<Box<dyn BufRead+'doc>>,
+    pub(crate) stdout:Box<dyn Write+'doc>,
+    pub(crate) stderr:Box<dyn Write+'doc>,
+}
+impl<'doc> Streams<'doc> {
+    pub(crate) fn set_stdin<R:BufRead+'doc>(&mut self,input:R) {
+        self.stdin=Some(Box::new(input));
+    }
+    pub(crate) fn set_stdout<W:Write+'doc>(&mut self,output:W) {
+        self.stdout=Box::new(output);
+    }
+    pub(crate) fn set_stderr<W:Write+'doc>(&mut self,output:W) {
+        self.stderr=Box::new(output);
+    }
+    /// Write `data` like a print operation does.
+    pub(crate) fn print(&mut self,data:&Data<'doc>)->Result<(),RuntimeErrorKind> {
+        return write!(self.stdout,"{}",data).map_err(io_error);
+    }
+    pub(crate) fn flush(&mut self)->Result<(),RuntimeErrorKind> {
+        self.stdout.flush().map_err(io_error)?;
+        return self.stderr.flush().map_err(io_error);
+    }
+    /// Read what an input operation asks for. The output is flushed first, so prompts show up
+    /// before the program waits for an answer.
+    pub(crate) fn read(&mut self,kind:InputKind)->Result<Data<'doc>,RuntimeErrorKind> {
+        self.flush()?;
+        return match &mut self.stdin {
+            Some(input)=>read_input(input,kind),
+            None=>read_input(&mut stdin().lock(),kind),
+        };
+    }
+}
+impl<'doc> Default for Streams<'doc> {
+    fn default()->Self {
+        Streams {
+            stdin:None,
+            stdout:Box::new(BufWriter::new(stdout())),
+            stderr:Box::new(BufWriter::new(stderr())),
+        }
+    }
+}
+impl<'doc> Debug for Streams<'doc> {
+    fn fmt(&self,f:&mut Formatter)->FmtResult {
+        write!(f,"Streams")
+    }
+}
+
+
+fn read_input<'doc,R:BufRead+?Sized>(input:&mut R,kind:InputKind)->Result<Data<'doc>,RuntimeErrorKind> {
+    match kind {
+        InputKind::Line|InputKind::Number=>{
+            let mut line=String::new();
+            if input.read_line(&mut line).map_err(io_error)?==0 {
+                return Ok(Data::None);
+            }
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+            if kind==InputKind::Line {
+                return Ok(Data::Str(line));
+            }
+            if let Ok(n)=line.trim().parse::<i64>() {
+                return Ok(Data::Int(n));
+            }
+            return match line.trim().parse::<f64>() {
+                Ok(n)=>Ok(Data::Number(n)),
+                Err(_)=>Err(RuntimeErrorKind::InvalidNumber(line)),
+            };
+        },
+        InputKind::All=>{
+            let mut all=String::new();
+            input.read_to_string(&mut all).map_err(io_error)?;
+            return Ok(Data::Str(all));
+        },
+        InputKind::Eof=>return Ok(Data::Bool(input.fill_buf().map_err(io_error)?.is_empty())),
+    }
+}
@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use crate::ast::Class;
+
+
+/// The function a call to the unknown `class>function` most likely meant, like `0>MAIN` for
+/// `0>MIAN`. Functions of the same class are preferred, then the closest name.
+pub(crate) fn similar_function(classes:&HashMap<u32,Class>,class:u32,function:&str)->Option<String> {
+    let mut best:Option<(bool,usize,u32,&str)>=None;
+    for (number,c) in classes.iter() {
+        for name in c.functions.keys() {
+            let distance=distance(function,name);
+            // A third of the letters may differ, so short names don't match everything.
+            if distance>function.len().max(name.len())/3 {
+                continue;
+            }
+            if distance==0&&*number==class {
+                continue;
+            }
+            let candidate=(*number!=class,distance,*number,*name);
+            if best.map_or(true,|best|candidate<best) {
+                best=Some(candidate);
+            }
+        }
+    }
+    return best.map(|(_,_,number,name)|format!("{}>{}",number,name));
+}
+/// How many letters have to be inserted, removed, changed or swapped to turn `a` into `b`.
+fn distance(a:&str,b:&str)->usize {
+    let a=a.as_bytes();
+    let b=b.as_bytes();
+    // Three rows of the table are enough, the one before the last is needed for swaps.
+    let mut before:Vec<usize>=Vec::new();
+    let mut last:Vec<usize>=(0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut row=vec![i;b.len()+1];
+        for j in 1..=b.len() {
+            let cost=if a[i-1]==b[j-1] {0} else {1};
+            row[j]=(last[j]+1).min(row[j-1]+1).min(last[j-1]+cost);
+            if i>1&&j>1&&a[i-1]==b[j-2]&&a[i-2]==b[j-1] {
+                row[j]=row[j].min(before[j-2]+1);
+            }
+        }
+        before=last;
+        last=row;
+    }
+    return last[b.len()];
+}
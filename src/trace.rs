@@ -0,0 +1,96 @@
+use std::io::Write;
+use crate::{
+    ast::Operation,
+    data::Data,
+    error::RuntimeErrorKind,
+    interpreter::{
+        Hook,
+        Interpreter,
+    },
+    json::Json,
+};
+
+
+#[derive(Clone,Copy,Debug,PartialEq)]
+pub enum TraceFormat {
+    /// One aligned line per operation, like `  1 file.happy:3:9 Add a: 1 -> 3, b: 2`.
+    Human,
+    /// One JSON object per line.
+    Json,
+}
+
+
+/// A [`Hook`] that writes a line for every operation once it ran, with its location, the call
+/// depth and the variables it touched before and after.
+pub struct Tracer<'doc> {
+    format:TraceFormat,
+    output:Box<dyn Write+'doc>,
+    /// The values of the touched variables before each running operation, innermost last.
+    before:Vec<Vec<(&'doc str,Data<'doc>)>>,
+}
+impl<'doc> Tracer<'doc> {
+    pub fn new<W:Write+'doc>(format:TraceFormat,output:W)->Self {
+        Tracer {
+            format,
+            output:Box::new(output),
+            before:Vec::new(),
+        }
+    }
+    fn write_human(&mut self,operation:&Operation<'doc>,depth:usize,variables:&[(&'doc str,Data<'doc>,Data<'doc>)])->std::io::Result<()> {
+        let span=operation.span;
+        write!(self.output,"{:>3} {}:{}:{} {}",depth,span.file,span.line,span.column,operation.kind.name())?;
+        for (i,(name,before,after)) in variables.iter().enumerate() {
+            write!(self.output,"{}{}: {}",if i==0 {" "} else {", "},name,before.quoted())?;
+            if before!=after||before.type_name()!=after.type_name() {
+                write!(self.output," -> {}",after.quoted())?;
+            }
+        }
+        return writeln!(self.output);
+    }
+    fn write_json(&mut self,operation:&Operation<'doc>,interpreter:&Interpreter<'doc>,variables:&[(&'doc str,Data<'doc>,Data<'doc>)])->std::io::Result<()> {
+        let span=operation.span;
+        let function=match interpreter.stack().last() {
+            Some(frame)=>frame.to_string().into(),
+            None=>Json::Null,
+        };
+        let variables=variables.iter()
+            .map(|(name,before,after)|Json::object([
+                ("name",(*name).into()),
+                ("before",before.into()),
+                ("after",after.into()),
+            ]))
+            .collect();
+        let line=Json::object([
+            ("op",operation.kind.name().into()),
+            ("file",span.file.into()),
+            ("line",span.line.into()),
+            ("column",span.column.into()),
+            ("depth",interpreter.stack().len().into()),
+            ("function",function),
+            ("variables",Json::Array(variables)),
+        ]);
+        return writeln!(self.output,"{}",line);
+    }
+}
+impl<'doc> Hook<'doc> for Tracer<'doc> {
+    fn operation(&mut self,operation:&Operation<'doc>,interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        let before=operation.kind.variables()
+            .into_iter()
+            .map(|name|(name,interpreter.variable(name).cloned().unwrap_or_default()))
+            .collect();
+        self.before.push(before);
+        return Ok(());
+    }
+    fn operation_done(&mut self,operation:&Operation<'doc>,_result:&Data<'doc>,interpreter:&mut Interpreter<'doc>)->Result<(),RuntimeErrorKind> {
+        let variables:Vec<_>=self.before.pop()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(name,before)|(name,before,interpreter.variable(name).cloned().unwrap_or_default()))
+            .collect();
+        let written=match self.format {
+            TraceFormat::Human=>self.write_human(operation,interpreter.stack().len(),&variables),
+            TraceFormat::Json=>self.write_json(operation,interpreter,&variables),
+        };
+        return written.map_err(|e|RuntimeErrorKind::Io(e.to_string()));
+    }
+}
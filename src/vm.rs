@@ -0,0 +1,301 @@
+use std::io::{
+    BufRead,
+    Write,
+};
+use crate::{
+    bytecode::*,
+    data::Data,
+    error::*,
+    interpreter::Limits,
+    span::Span,
+    streams::Streams,
+};
+
+
+type RunResult<'doc,T>=std::result::Result<T,RuntimeError<'doc>>;
+
+
+/// A function running in the VM.
+#[derive(Debug)]
+struct CallFrame<'doc> {
+    function:usize,
+    pc:usize,
+    /// Where the variables of this function start in `Vm::slots`.
+    slots:usize,
+    called_at:Option<Span<'doc>>,
+}
+
+
+/// A stack machine running [`Bytecode`]. Calls don't recurse on the native stack, but the VM
+/// keeps to the same [`Limits`] as the interpreter, so both stop a program at the same point.
+#[derive(Debug,Default)]
+pub struct Vm<'doc> {
+    stack:Vec<Data<'doc>>,
+    slots:Vec<Data<'doc>>,
+    frames:Vec<CallFrame<'doc>>,
+    limits:Limits,
+    /// How many operations ran so far in this run, only counted when they are limited.
+    operations:u64,
+    /// The memory used by variables, only counted when it is limited. Values on `stack` are on
+    /// their way into a variable or a call and are counted once they get there, the way the
+    /// interpreter counts a value once it is assigned, so both stop at the same operation.
+    memory:usize,
+    streams:Streams<'doc>,
+}
+impl<'doc> Vm<'doc> {
+    pub fn new()->Self {
+        Self::default()
+    }
+    pub fn set_limits(&mut self,limits:Limits) {
+        self.limits=limits;
+    }
+    pub fn limits(&self)->Limits {
+        return self.limits;
+    }
+    /// Read input instructions from `input` instead of stdin.
+    pub fn set_stdin<R:BufRead+'doc>(&mut self,input:R) {
+        self.streams.set_stdin(input);
+    }
+    /// Write printed values to `output` instead of stdout.
+    pub fn set_stdout<W:Write+'doc>(&mut self,output:W) {
+        self.streams.set_stdout(output);
+    }
+    pub fn set_stderr<W:Write+'doc>(&mut self,output:W) {
+        self.streams.set_stderr(output);
+    }
+    fn pop(&mut self)->Data<'doc> {
+        return self.stack.pop().expect("the compiler keeps the stack balanced");
+    }
+    fn slot(&mut self,slot:usize)->&mut Data<'doc> {
+        let base=self.frames.last().unwrap().slots;
+        return &mut self.slots[base+slot];
+    }
+    /// Call `function` with the arguments on the stack, unless that nests calls too deeply or the
+    /// arguments take more memory than allowed.
+    fn call(&mut self,bytecode:&Bytecode<'doc>,function:usize,called_at:Option<Span<'doc>>)->Result<(),RuntimeErrorKind> {
+        if let Some(max)=self.limits.call_depth {
+            // The frame of `main` isn't a call.
+            if self.frames.len()-1>=max {
+                return Err(RuntimeErrorKind::CallDepthLimit(max));
+            }
+        }
+        if self.limits.memory.is_some() {
+            let arguments=&self.stack[self.stack.len()-bytecode.functions[function].parameters..];
+            let added=arguments.iter().map(Data::size).sum();
+            self.grow(added,0);
+            self.check_memory()?;
+        }
+        self.push_frame(bytecode,function,called_at);
+        return Ok(());
+    }
+    fn push_frame(&mut self,bytecode:&Bytecode<'doc>,function:usize,called_at:Option<Span<'doc>>) {
+        let compiled=&bytecode.functions[function];
+        let slots=self.slots.len();
+        let arguments=self.stack.split_off(self.stack.len()-compiled.parameters);
+        self.slots.extend(arguments);
+        self.slots.resize(slots+compiled.slots.len(),Data::None);
+        self.frames.push(CallFrame{function,pc:0,slots,called_at});
+    }
+    fn grow(&mut self,added:usize,removed:usize) {
+        self.memory=(self.memory+added).saturating_sub(removed);
+    }
+    /// An error if the variables use more memory than allowed.
+    fn check_memory(&self)->Result<(),RuntimeErrorKind> {
+        match self.limits.memory {
+            Some(max) if self.memory>max=>Err(RuntimeErrorKind::MemoryLimit(max)),
+            _=>Ok(()),
+        }
+    }
+    /// Change a variable in place. When memory is limited, `measure` is called before and after to
+    /// see how much the part of the variable that changed grew.
+    fn update<M,F>(&mut self,slot:usize,measure:M,update:F)->Result<(),RuntimeErrorKind>
+    where M:Fn(&Data<'doc>)->usize,F:FnOnce(&mut Data<'doc>)->Result<(),RuntimeErrorKind> {
+        if self.limits.memory.is_none() {
+            return update(self.slot(slot));
+        }
+        let before=measure(self.slot(slot));
+        update(self.slot(slot))?;
+        let after=measure(self.slot(slot));
+        self.grow(after,before);
+        return self.check_memory();
+    }
+    /// The error for the instruction that just ran, with the call stack outside of `main`.
+    fn error(&self,bytecode:&Bytecode<'doc>,kind:RuntimeErrorKind)->RuntimeError<'doc> {
+        let frame=self.frames.last().unwrap();
+        let span=bytecode.functions[frame.function].spans[frame.pc-1];
+        return self.error_at(bytecode,kind,span);
+    }
+    /// Like [`Vm::error`], but pointing at `span`.
+    fn error_at(&self,bytecode:&Bytecode<'doc>,kind:RuntimeErrorKind,span:Option<Span<'doc>>)->RuntimeError<'doc> {
+        let stack=self.frames.iter()
+            .filter(|frame|frame.function!=bytecode.main)
+            .map(|frame|{
+                let function=&bytecode.functions[frame.function];
+                Frame{class:function.class,function:function.name,called_at:frame.called_at}
+            })
+            .collect();
+        return RuntimeError{kind,span,stack};
+    }
+    /// Run the top-level statements of `bytecode`.
+    pub fn run(&mut self,bytecode:&Bytecode<'doc>)->RunResult<'doc,()> {
+        self.stack.clear();
+        self.slots.clear();
+        self.frames.clear();
+        self.operations=0;
+        self.memory=0;
+        self.push_frame(bytecode,bytecode.main,None);
+        let result=self.execute(bytecode);
+        if result.is_err() {
+            self.stack.clear();
+            self.slots.clear();
+            self.frames.clear();
+        }
+        let flushed=self.streams.flush();
+        result?;
+        return flushed.map_err(|kind|RuntimeError{kind,span:None,stack:Vec::new()});
+    }
+    fn execute(&mut self,bytecode:&Bytecode<'doc>)->RunResult<'doc,()> {
+        loop {
+            let frame=self.frames.last_mut().unwrap();
+            let function=&bytecode.functions[frame.function];
+            let instruction=&function.code[frame.pc];
+            let started=&function.operations[frame.pc];
+            frame.pc+=1;
+            if let Some(max)=self.limits.operations {
+                for span in started {
+                    self.operations+=1;
+                    if self.operations>max {
+                        return Err(self.error_at(bytecode,RuntimeErrorKind::OperationLimit(max),Some(*span)));
+                    }
+                }
+            }
+            let result=match instruction {
+                Instruction::Const(data)=>{
+                    self.stack.push(data.clone());
+                    Ok(())
+                },
+                Instruction::Load(slot)=>{
+                    let data=self.slot(*slot).clone();
+                    self.stack.push(data);
+                    Ok(())
+                },
+                Instruction::Store(slot)=>{
+                    let data=self.pop();
+                    self.update(*slot,Data::size,|var|{
+                        *var=data;
+                        Ok(())
+                    })
+                },
+                Instruction::Discard=>{
+                    self.pop();
+                    Ok(())
+                },
+                Instruction::Binary(slot,op)=>{
+                    let other=self.pop();
+                    let op=*op;
+                    self.update(*slot,Data::size,|var|op.apply(var,other))
+                },
+                Instruction::Not(slot)=>self.update(*slot,Data::size,Data::not),
+                Instruction::Print(slot)=>{
+                    let base=self.frames.last().unwrap().slots;
+                    self.streams.print(&self.slots[base+slot])
+                },
+                Instruction::Input(slot,kind)=>match self.streams.read(*kind) {
+                    Ok(data)=>self.update(*slot,Data::size,|var|{
+                        *var=data;
+                        Ok(())
+                    }),
+                    Err(kind)=>Err(kind),
+                },
+                Instruction::List(length)=>{
+                    let items=self.stack.split_off(self.stack.len()-length);
+                    self.stack.push(Data::List(items));
+                    Ok(())
+                },
+                Instruction::Map(keys)=>{
+                    let values=self.stack.split_off(self.stack.len()-keys.len());
+                    self.stack.push(Data::Map(keys.iter().cloned().zip(values).collect()));
+                    Ok(())
+                },
+                Instruction::Get(slot)=>{
+                    let index=self.pop();
+                    self.slot(*slot).get(&index).map(|data|self.stack.push(data))
+                },
+                Instruction::Set(slot)=>{
+                    let value=self.pop();
+                    let index=self.pop();
+                    self.update(*slot,|list|list.size_at(&index),|list|list.set(&index,value))
+                },
+                Instruction::Append(slot)=>{
+                    let value=self.pop();
+                    let added=if self.limits.memory.is_some() {value.item_size()} else {0};
+                    self.slot(*slot).push(value).and_then(|()|{
+                        self.grow(added,0);
+                        self.check_memory()
+                    })
+                },
+                Instruction::Pop(slot)=>self.slot(*slot).pop().map(|data|{
+                    if self.limits.memory.is_some() {
+                        self.grow(0,data.item_size());
+                    }
+                    self.stack.push(data);
+                }),
+                Instruction::Remove(slot)=>{
+                    let index=self.pop();
+                    let removed=if self.limits.memory.is_some() {self.slot(*slot).size_at(&index)} else {0};
+                    self.slot(*slot).remove(&index).map(|()|self.grow(0,removed))
+                },
+                Instruction::Contains(slot)=>{
+                    let key=self.pop();
+                    self.slot(*slot).contains(&key).map(|found|self.stack.push(Data::Bool(found)))
+                },
+                Instruction::Keys(slot)=>self.slot(*slot).keys().map(|keys|self.stack.push(keys)),
+                Instruction::Length(slot)=>{
+                    self.slot(*slot).length().map(|length|self.stack.push(Data::Int(length as i64)))
+                },
+                Instruction::Call(target)=>{
+                    // Calls from `main` are the top-level statements, which nothing called.
+                    let called_at=if frame.function==bytecode.main {None} else {function.spans[frame.pc-1]};
+                    self.call(bytecode,*target,called_at)
+                },
+                Instruction::Return=>{
+                    let data=self.pop();
+                    let frame=self.frames.pop().unwrap();
+                    if self.limits.memory.is_some() {
+                        let removed=self.slots[frame.slots..].iter().map(Data::size).sum();
+                        self.grow(0,removed);
+                    }
+                    self.slots.truncate(frame.slots);
+                    if self.frames.is_empty() {
+                        return Ok(());
+                    }
+                    self.stack.push(data);
+                    Ok(())
+                },
+                Instruction::Jump(to)=>{
+                    frame.pc=*to;
+                    Ok(())
+                },
+                Instruction::JumpUnlessTrue(to)=>{
+                    let to=*to;
+                    if self.pop()!=Data::Bool(true) {
+                        self.frames.last_mut().unwrap().pc=to;
+                    }
+                    Ok(())
+                },
+                Instruction::Fail(kind)=>Err(kind.clone()),
+            };
+            if let Err(kind)=result {
+                return Err(self.error(bytecode,kind));
+            }
+        }
+    }
+}
+
+
+impl<'doc> Bytecode<'doc> {
+    /// Run the top-level statements with a fresh [`Vm`].
+    pub fn run(&self)->RunResult<'doc,()> {
+        return Vm::new().run(self);
+    }
+}
@@ -0,0 +1,58 @@
+//! Helpers shared by the tests that run whole programs.
+#![allow(dead_code)]
+use std::{
+    env,
+    fs,
+    io::Write,
+    process::{
+        Command,
+        Stdio,
+    },
+    sync::atomic::{
+        AtomicUsize,
+        Ordering,
+    },
+};
+use happy::{
+    parse,
+    Interpreter,
+    RuntimeErrorKind,
+};
+
+
+/// Numbers the program files so tests running at the same time don't share one.
+static PROGRAMS:AtomicUsize=AtomicUsize::new(0);
+
+
+/// What `source` printed when `happy run` ran it with `input` as its stdin, or what `happy`
+/// printed to stderr when the program failed.
+pub fn run(source:&str,input:&str)->Result<String,String> {
+    let program=PROGRAMS.fetch_add(1,Ordering::Relaxed);
+    let path=env::temp_dir().join(format!("happy-test-{}-{}.happy",std::process::id(),program));
+    fs::write(&path,source).unwrap();
+    let mut child=Command::new(env!("CARGO_BIN_EXE_happy"))
+        .arg("run")
+        .arg(&path)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not start `happy`");
+    // A program that stops before reading all of its input closes stdin early.
+    let _=child.stdin.take().unwrap().write_all(input.as_bytes());
+    let output=child.wait_with_output().unwrap();
+    let _=fs::remove_file(&path);
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).into_owned());
+    }
+    return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+}
+/// The kind of error `source` stops with when it runs, `None` when it runs to the end.
+pub fn error(source:&str)->Option<RuntimeErrorKind> {
+    let program=parse(source,"test.happy").unwrap();
+    return program.run_with(&mut Interpreter::new()).err().map(|error|error.kind);
+}
+/// A program whose function runs `operations` and prints `r`.
+pub fn function(operations:&str)->String {
+    return format!("0:\n    A:\n        {},\n        r,\n    ;\n;\n0>A\n",operations);
+}
@@ -0,0 +1,84 @@
+//! Runs programs in the [`Debugger`] with commands as its input and checks where it pauses and
+//! what it shows.
+use happy::{
+    parse,
+    Debugger,
+    Interpreter,
+    RuntimeErrorKind,
+};
+
+
+/// What the debugger showed and what the program printed for `source`, given `commands`, and
+/// whether the program finished instead of being stopped.
+fn debug(source:&str,commands:&str)->(String,String,bool) {
+    let program=parse(source,"test.happy").unwrap();
+    let mut shown=Vec::new();
+    let mut printed=Vec::new();
+    let finished={
+        let mut debugger=Debugger::new(&program,source);
+        debugger.set_input(commands.as_bytes());
+        debugger.set_output(&mut shown);
+        let mut interpreter=Interpreter::with_hook(debugger);
+        interpreter.set_stdout(&mut printed);
+        match program.run_with(&mut interpreter) {
+            Ok(())=>true,
+            Err(e)=>{
+                assert_eq!(e.kind,RuntimeErrorKind::Stopped);
+                false
+            },
+        }
+    };
+    return (String::from_utf8(shown).unwrap(),String::from_utf8(printed).unwrap(),finished);
+}
+
+
+#[test]
+fn line_breakpoint_pauses_every_round() {
+    let source="\
+0:
+    A:
+        i=0,
+        n=3,
+        one=1,
+        c=i,
+        (c<n)@{i+one,c=i,},
+    ;
+;
+0>A
+";
+    let (shown,_,finished)=debug(source,"break 7\ncontinue\ncontinue\ncontinue\ncontinue\ncontinue\n");
+    assert!(shown.contains(":3:9\n"));
+    // Once when the loop starts, then every time its condition is checked again.
+    assert_eq!(shown.matches(":7:").count(),4);
+    assert!(finished);
+}
+#[test]
+fn function_breakpoint_pauses_in_the_function() {
+    let source="0:\n    A:\n        r=0>B,\n        r,\n    ;\n    B:\n        ^2,\n    ;\n;\n0>A\n";
+    let (shown,printed,_)=debug(source,"break 0>B\ncontinue\nstack\ncontinue\n");
+    assert!(shown.contains("Breakpoint 0>B"));
+    assert!(shown.contains("In 0>B at "));
+    assert!(shown.contains("#1 0>A called from the top level"));
+    assert_eq!(printed,"2");
+}
+#[test]
+fn step_goes_into_calls_and_next_over_them() {
+    let source="0:\n    A:\n        r=0>B,\n        r,\n    ;\n    B:\n        ^2,\n    ;\n;\n0>A\n";
+    let (stepped,_,finished)=debug(source,"step\nstack\nquit\n");
+    assert!(stepped.contains("In 0>B at "));
+    assert!(stepped.contains("#0 0>B called at "));
+    assert!(!finished);
+    let (nexted,_,_)=debug(source,"next\nquit\n");
+    assert!(!nexted.contains("In 0>B"));
+    assert!(nexted.contains(":4:9\n"));
+}
+#[test]
+fn set_changes_a_variable() {
+    let source="0:\n    A:\n        a=1,\n        s=\"b\",\n        a,\n        c,\n    ;\n;\n0>A\n";
+    let (shown,printed,_)=debug(source,"next\nset a=[a,2]\nprint a\nset c=3\nset b=1\ncontinue\n");
+    assert!(shown.contains("a = [1, 2]"));
+    // `b` is only in a string, not a variable.
+    assert!(shown.contains("The program doesn't use `b`"));
+    // `c` didn't exist before `set` created it.
+    assert_eq!(printed,"[1, 2]3");
+}
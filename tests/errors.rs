@@ -0,0 +1,28 @@
+//! Checks the JSON form of errors that `--error-format=json` prints.
+use happy::parse;
+
+
+#[test]
+fn parse_error_as_json() {
+    let source="0:\n    A:\n        b+,\n    ;\n;\n";
+    let errors=parse(source,"test.happy").unwrap_err();
+    assert_eq!(errors.len(),1);
+    assert_eq!(errors[0].to_json(source),concat!(
+        r#"{"type":"parse","severity":"error","code":"H0004","message":"Expected a variable name","#,
+        r#""help":"variable names must be lowercase letters, like `count`","file":"test.happy","#,
+        r#""span":{"start":{"offset":20,"line":3,"column":11},"end":{"offset":21,"line":3,"column":12}},"#,
+        r#""stack":[]}"#,
+    ));
+}
+#[test]
+fn runtime_error_as_json() {
+    let source="0:\n    A:\n        a=1,\n        b=0,\n        a/b,\n    ;\n;\n0>A\n";
+    let program=parse(source,"test.happy").unwrap();
+    let error=program.run().unwrap_err();
+    assert_eq!(error.to_json(source),concat!(
+        r#"{"type":"runtime","severity":"error","code":"H0106","message":"Division by zero","help":"check that the divisor isn't 0 before dividing","#,
+        r#""file":"test.happy","#,
+        r#""span":{"start":{"offset":36,"line":5,"column":9},"end":{"offset":37,"line":5,"column":10}},"#,
+        r#""stack":[{"function":"0>A","called_at":null}]}"#,
+    ));
+}
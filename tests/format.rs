@@ -0,0 +1,52 @@
+//! Formats programs and checks that the result parses, is stable and keeps strings as written.
+use std::fs;
+use happy::parse;
+
+
+fn format(source:&str)->String {
+    let program=parse(source,"test.happy").unwrap();
+    return program.format();
+}
+
+
+#[test]
+fn one_line_program() {
+    let formatted=format(r#"0:A(x , y):a="An \"example\"\x21\n",(x<y)?{a}:{^ y},x;B:;; 0 > A(1,2)"#);
+    assert_eq!(formatted,"\
+0:
+    A(x,y):
+        a=\"An \\\"example\\\"\\x21\\n\",
+        (x<y)?{
+            a,
+        }:{
+            ^y,
+        },
+        x,
+    ;
+    B:
+    ;
+;
+0>A(1,2)
+");
+}
+#[test]
+fn formatting_is_stable() {
+    for entry in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"),"/examples")).unwrap() {
+        let path=entry.unwrap().path();
+        let source=fs::read_to_string(&path).unwrap();
+        let formatted=format(&source);
+        assert_eq!(format(&formatted),formatted,"formatting {} twice changed it",path.display());
+    }
+}
+#[test]
+fn calls_that_go_nowhere_are_formatted() {
+    assert_eq!(format("0:A:0>PRNIT,x=1>A;;0>A"),"\
+0:
+    A:
+        0>PRNIT,
+        x=1>A,
+    ;
+;
+0>A
+");
+}
@@ -0,0 +1,86 @@
+//! Checks that calls pass their arguments to the parameters of a function and get back what it
+//! returns.
+mod common;
+
+use common::run;
+
+
+#[test]
+fn arguments_and_return_values() {
+    let source="\
+0:
+    A:
+        x=1,
+        r=0>ADD(x,2),
+        r,
+    ;
+    ADD(x,y):
+        x+y,
+        ^x,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source,""),Ok("3".to_string()));
+}
+#[test]
+fn recursion_returns_through_every_call() {
+    let source="\
+0:
+    A:
+        r=0>F(10),
+        r,
+    ;
+    F(n):
+        one=1,
+        c=n,
+        (c<=one)?{
+            ^one,
+        },
+        m=n,
+        m-one,
+        r=0>F(m),
+        r*n,
+        ^r,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source,""),Ok("3628800".to_string()));
+}
+#[test]
+fn arguments_are_copied() {
+    let source="\
+0:
+    A:
+        x=1,
+        0>INC(x),
+        x,
+    ;
+    INC(x):
+        one=1,
+        x+one,
+        x,
+    ;
+;
+0>A
+";
+    // The function sees its own copy go up, the caller still has its 1.
+    assert_eq!(run(source,""),Ok("21".to_string()));
+}
+#[test]
+fn functions_without_a_return_give_none() {
+    let source="\
+0:
+    A:
+        r=0>B,
+        r,
+    ;
+    B:
+        x=1,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source,""),Ok("None".to_string()));
+}
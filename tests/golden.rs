@@ -0,0 +1,144 @@
+//! Runs every `.happy` program in `tests/golden`, `examples` and `program.happy` with the `happy`
+//! binary, once with the interpreter and once with `--vm`, and compares what it printed and its
+//! exit code with the expectation files for it:
+//!
+//! - `<name>.stdout` and `<name>.stderr`, empty when missing
+//! - `<name>.status`, the exit code, `0` when missing
+//! - `<name>.stdin`, the input for the program, empty when missing
+//! - `<name>.args`, options for `happy run` like `--max-depth=3`, none when missing
+//!
+//! The expectation files sit next to the programs in `tests/golden`; the ones for programs
+//! elsewhere are in `tests/golden` under the same path, like `tests/golden/examples/loop.stdout`.
+//! Set `HAPPY_BLESS=1` to write the expectation files from what the programs do now with the
+//! interpreter.
+use std::{
+    env,
+    fs,
+    io::Write,
+    path::{
+        Path,
+        PathBuf,
+    },
+    process::{
+        Command,
+        Stdio,
+    },
+};
+
+
+const GOLDEN:&str="tests/golden";
+
+
+#[derive(Debug,PartialEq)]
+struct Outcome {
+    stdout:String,
+    stderr:String,
+    status:i32,
+}
+
+
+fn root()->&'static Path {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+/// Every program to run, relative to the crate root.
+fn programs()->Vec<PathBuf> {
+    let mut programs=vec![PathBuf::from("program.happy")];
+    collect(Path::new("examples"),&mut programs);
+    collect(Path::new(GOLDEN),&mut programs);
+    programs.sort();
+    return programs;
+}
+fn collect(dir:&Path,programs:&mut Vec<PathBuf>) {
+    for entry in fs::read_dir(root().join(dir)).unwrap() {
+        let path=dir.join(entry.unwrap().file_name());
+        if root().join(&path).is_dir() {
+            collect(&path,programs);
+        } else if path.extension().is_some_and(|ext|ext=="happy") {
+            programs.push(path);
+        }
+    }
+}
+/// The expectation files of `program` without their extension.
+fn expectations(program:&Path)->PathBuf {
+    let stem=program.with_extension("");
+    if stem.starts_with(GOLDEN) {
+        return stem;
+    }
+    return Path::new(GOLDEN).join(stem);
+}
+fn read(path:&Path)->Option<String> {
+    return fs::read_to_string(root().join(path)).ok();
+}
+/// Write `contents` to `path`, or remove it when it would only hold the default.
+fn bless(path:&Path,contents:&str,default:&str) {
+    let path=root().join(path);
+    if contents==default {
+        let _=fs::remove_file(path);
+        return;
+    }
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path,contents).unwrap();
+}
+fn run(program:&Path,input:&str,options:&str,vm:bool)->Outcome {
+    let mut command=Command::new(env!("CARGO_BIN_EXE_happy"));
+    command.current_dir(root()).arg("run").args(options.split_whitespace());
+    if vm {
+        command.arg("--vm");
+    }
+    let mut child=command.arg(program)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("could not start `happy`");
+    child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+    let output=child.wait_with_output().unwrap();
+    return Outcome {
+        stdout:String::from_utf8_lossy(&output.stdout).into_owned(),
+        stderr:String::from_utf8_lossy(&output.stderr).into_owned(),
+        status:output.status.code().unwrap_or(-1),
+    };
+}
+
+
+#[test]
+fn golden() {
+    let blessing=env::var_os("HAPPY_BLESS").is_some();
+    let mut failures=Vec::new();
+    for program in programs() {
+        let base=expectations(&program);
+        let input=read(&base.with_extension("stdin")).unwrap_or_default();
+        let options=read(&base.with_extension("args")).unwrap_or_default();
+        let found=run(&program,&input,&options,false);
+        if blessing {
+            bless(&base.with_extension("stdout"),&found.stdout,"");
+            bless(&base.with_extension("stderr"),&found.stderr,"");
+            bless(&base.with_extension("status"),&format!("{}\n",found.status),"0\n");
+            continue;
+        }
+        let expected=Outcome {
+            stdout:read(&base.with_extension("stdout")).unwrap_or_default(),
+            stderr:read(&base.with_extension("stderr")).unwrap_or_default(),
+            status:read(&base.with_extension("status")).map_or(0,|status|status.trim().parse().unwrap()),
+        };
+        let found_vm=run(&program,&input,&options,true);
+        for (engine,found) in [("",found),(" with --vm",found_vm)] {
+            if found!=expected {
+                failures.push(format!(
+                    "{}{}\n--- expected (status {})\n{}{}--- found (status {})\n{}{}",
+                    program.display(),
+                    engine,
+                    expected.status,
+                    expected.stdout,
+                    expected.stderr,
+                    found.status,
+                    found.stdout,
+                    found.stderr,
+                ));
+            }
+        }
+    }
+    if failures.len()>0 {
+        panic!("{} program(s) did not do what was expected:\n\n{}",failures.len(),failures.join("\n\n"));
+    }
+}
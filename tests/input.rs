@@ -0,0 +1,60 @@
+//! Checks the input operations: `a$` reads a line, `a$#` a number, `a$$` everything left and `a$?`
+//! whether the input is used up.
+mod common;
+
+use common::run;
+
+
+#[test]
+fn lines_lose_their_line_ending() {
+    let source="\
+0:
+    A:
+        a$,
+        b$,
+        c$,
+        a,
+        b,
+        c,
+    ;
+;
+0>A
+";
+    // Reading after the end gives none.
+    assert_eq!(run(source,"one\r\ntwo"),Ok("onetwoNone".to_string()));
+}
+#[test]
+fn numbers_are_ints_or_numbers() {
+    let source="\
+0:
+    A:
+        n$#,
+        m$#,
+        n+m,
+        n,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source," 40 \n2\n"),Ok("42".to_string()));
+    assert_eq!(run(source,"40\n2.5\n"),Ok("42.5".to_string()));
+    assert!(run(source,"forty\n2\n").unwrap_err().contains("Expected a number but read `forty`"));
+}
+#[test]
+fn all_input_and_eof() {
+    let source="\
+0:
+    A:
+        e$?,
+        e,
+        a$$,
+        a,
+        e$?,
+        e,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source,"x\ny\n"),Ok("falsex\ny\ntrue".to_string()));
+    assert_eq!(run(source,""),Ok("truetrue".to_string()));
+}
@@ -0,0 +1,38 @@
+//! Checks list literals and the list operations: indexing, length, push, pop and joining lists with
+//! `+`.
+mod common;
+
+use common::{
+    error,
+    function,
+    run,
+};
+use happy::RuntimeErrorKind;
+
+
+#[test]
+fn list_operations() {
+    let source=function("x=3,r=[1,2,x],r<<\"four\",r>>last,n=#r,r<<n,first=r[0],r[1]=first");
+    assert_eq!(run(&source,""),Ok("[1, 1, 3, 3]".to_string()));
+    let source=function("l=[1,\"two\"],l>>r");
+    assert_eq!(run(&source,""),Ok("two".to_string()));
+}
+#[test]
+fn lists_join_with_plus() {
+    let source=function("r=[1],b=[\"x\",[2]],r+b");
+    assert_eq!(run(&source,""),Ok("[1, \"x\", [2]]".to_string()));
+}
+#[test]
+fn lists_compare_item_by_item() {
+    let source=function("a=[1,2],b=[1,2],a==b,c=[1,2],d=[1,3],c<d,e=[1],f=[1,2],e>=f,r=[a,c,e]");
+    assert_eq!(run(&source,""),Ok("[true, true, false]".to_string()));
+}
+#[test]
+fn list_errors() {
+    let source=function("l=[1],r=l[1]");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::IndexOutOfRange{index:1,length:1}));
+    let source=function("l=[],l>>r");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::EmptyList));
+    let source=function("l=[1],r=l[\"a\"]");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::InvalidIndex("a".to_string())));
+}
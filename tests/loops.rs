@@ -0,0 +1,88 @@
+//! Checks that loops repeat while their condition holds, and that `@>` and `@<` leave a loop or
+//! go on with its next round.
+mod common;
+
+use common::{
+    error,
+    run,
+};
+use happy::RuntimeErrorKind;
+
+
+/// A program that prints the sum of the numbers from 1 to `n`.
+fn sum(n:u32)->String {
+    return format!("\
+0:
+    A:
+        i=0,
+        s=0,
+        one=1,
+        n={},
+        c=i,
+        (c<n)@{{
+            i+one,
+            s+i,
+            c=i,
+        }},
+        s,
+    ;
+;
+0>A
+",n);
+}
+
+
+#[test]
+fn loops_repeat_while_the_condition_holds() {
+    assert_eq!(run(&sum(10),""),Ok("55".to_string()));
+    assert_eq!(run(&sum(0),""),Ok("0".to_string()));
+}
+#[test]
+fn long_loops_do_not_recurse() {
+    assert_eq!(run(&sum(100_000),""),Ok("5000050000".to_string()));
+}
+#[test]
+fn break_and_continue() {
+    let source="\
+0:
+    A:
+        i=0,
+        s=0,
+        one=1,
+        two=2,
+        n=100,
+        stop=7,
+        c=i,
+        (c<n)@{
+            i+one,
+            c=i,
+            d=i,
+            (d>stop)?{
+                @>,
+            },
+            odd=i,
+            odd//two,
+            (odd!=one)?{
+                @<,
+            },
+            s+i,
+        },
+        s,
+    ;
+;
+0>A
+";
+    assert_eq!(run(source,""),Ok("16".to_string()));
+}
+#[test]
+fn break_outside_a_loop_is_an_error() {
+    let source="\
+0:
+    A:
+        @>,
+    ;
+;
+0>A
+";
+    assert_eq!(error(source),Some(RuntimeErrorKind::OutsideLoop("@>")));
+}
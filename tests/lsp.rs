@@ -0,0 +1,62 @@
+//! Talks to the language server through in-memory streams, like an editor would.
+use happy::LanguageServer;
+
+
+const PROGRAM:&str="0:\\n    A:\\n        0>B,\\n    ;\\n    B(x):\\n        x,\\n    ;\\n;\\n0>A\\n";
+
+
+fn message(body:&str)->String {
+    format!("Content-Length: {}\r\n\r\n{}",body.len(),body)
+}
+fn open(text:&str)->String {
+    message(&format!(
+        r#"{{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{{"textDocument":{{"uri":"file:///test.happy","languageId":"happy","version":1,"text":"{}"}}}}}}"#,
+        text,
+    ))
+}
+fn request(id:usize,method:&str,line:usize,character:usize)->String {
+    message(&format!(
+        r#"{{"jsonrpc":"2.0","id":{},"method":"{}","params":{{"textDocument":{{"uri":"file:///test.happy"}},"position":{{"line":{},"character":{}}}}}}}"#,
+        id,
+        method,
+        line,
+        character,
+    ))
+}
+/// Run the server on `messages` followed by a clean shutdown and return what it wrote.
+fn serve(messages:&[String])->String {
+    let mut input=message(r#"{"jsonrpc":"2.0","id":0,"method":"initialize","params":{}}"#);
+    for message in messages {
+        input.push_str(message);
+    }
+    input.push_str(&message(r#"{"jsonrpc":"2.0","id":99,"method":"shutdown"}"#));
+    input.push_str(&message(r#"{"jsonrpc":"2.0","method":"exit"}"#));
+    let mut output=Vec::new();
+    let clean=LanguageServer::new().run(input.as_bytes(),&mut output).unwrap();
+    assert!(clean,"the server did not see the shutdown");
+    return String::from_utf8(output).unwrap();
+}
+
+
+#[test]
+fn definition_and_hover() {
+    let output=serve(&[
+        open(PROGRAM),
+        request(1,"textDocument/definition",2,10),
+        request(2,"textDocument/hover",2,10),
+        request(3,"textDocument/completion",2,10),
+    ]);
+    assert!(output.contains(r#""diagnostics":[]"#),"{}",output);
+    assert!(
+        output.contains(r#""id":1,"result":{"uri":"file:///test.happy","range":{"start":{"line":4,"character":4},"end":{"line":4,"character":5}}}"#),
+        "{}",
+        output,
+    );
+    assert!(output.contains(r#""value":"```happy\n0:\n    B(x):\n        x,\n    ;\n```""#),"{}",output);
+    assert!(output.contains(r#""label":"B","kind":3,"detail":"0>B(x)""#),"{}",output);
+}
+#[test]
+fn parse_errors_are_published() {
+    let output=serve(&[open("0:\\n    A:\\n        a=\\n")]);
+    assert!(output.contains(r#""severity":1,"source":"happy","code":"H00"#),"{}",output);
+}
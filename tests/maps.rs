@@ -0,0 +1,33 @@
+//! Checks map literals and the map operations: get, set, remove, contains and keys, and that maps
+//! always keep their keys in the same order.
+mod common;
+
+use common::{
+    error,
+    function,
+    run,
+};
+use happy::RuntimeErrorKind;
+
+
+#[test]
+fn keys_are_sorted() {
+    let source=function("r={\"b\":1, 2:\"two\", \"a\":3, 1.5:4}");
+    assert_eq!(run(&source,""),Ok("{1.5: 4, 2: \"two\", \"a\": 3, \"b\": 1}".to_string()));
+    let source=function("m={\"b\":1, 2:\"two\", \"a\":3},r=m[]");
+    assert_eq!(run(&source,""),Ok("[2, \"a\", \"b\"]".to_string()));
+}
+#[test]
+fn map_operations() {
+    let source=function("m={\"a\":1},m[\"c\"]=4,one=1,n=m[\"a\"],n+one,m[\"a\"]=n,h=m[\"c\"]?,m~\"c\",g=m[\"c\"]?,s=#m,r=[m,h,g,s]");
+    assert_eq!(run(&source,""),Ok("[{\"a\": 2}, true, false, 1]".to_string()));
+}
+#[test]
+fn map_errors() {
+    let source=function("m={},r=m[\"x\"]");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::MissingKey("\"x\"".to_string())));
+    let source=function("m={},r=m~1");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::MissingKey("1".to_string())));
+    let source=function("m={},k=[1],m[k]=1");
+    assert_eq!(error(&source),Some(RuntimeErrorKind::InvalidKey("list")));
+}
@@ -0,0 +1,67 @@
+//! Checks arithmetic on ints and numbers: ints stay ints, overflow is an error instead of wrapping
+//! around, and an int meeting a number becomes a number.
+use happy::{
+    parse,
+    Interpreter,
+    RuntimeErrorKind,
+};
+
+
+/// The result of `a<operation>b` as it is debug printed, like `Int(3)`.
+fn apply(a:&str,operation:&str,b:&str)->Result<String,RuntimeErrorKind> {
+    let source=format!("0:\n    A:\n        a={},\n        b={},\n        a{}b,\n        ^a,\n    ;\n;\n0>A\n",a,b,operation);
+    let program=parse(&source,"test.happy").unwrap();
+    return program.call(&program.statements[0],&mut Interpreter::new())
+        .map(|data|format!("{:?}",data))
+        .map_err(|error|error.kind);
+}
+
+
+#[test]
+fn ints_stay_ints() {
+    assert_eq!(apply("2","+","3"),Ok("Int(5)".to_string()));
+    assert_eq!(apply("2","-","3"),Ok("Int(-1)".to_string()));
+    assert_eq!(apply("4","*","-3"),Ok("Int(-12)".to_string()));
+    assert_eq!(apply("7","//","3"),Ok("Int(1)".to_string()));
+}
+#[test]
+fn int_division_rounds_toward_zero() {
+    assert_eq!(apply("7","/","2"),Ok("Int(3)".to_string()));
+    assert_eq!(apply("-7","/","2"),Ok("Int(-3)".to_string()));
+}
+#[test]
+fn overflow_is_an_error() {
+    assert_eq!(apply("9223372036854775807","+","1"),Err(RuntimeErrorKind::Overflow("+")));
+    assert_eq!(apply("-9223372036854775807","-","2"),Err(RuntimeErrorKind::Overflow("-")));
+    assert_eq!(apply("4611686018427387904","*","2"),Err(RuntimeErrorKind::Overflow("*")));
+    assert_eq!(apply("-9223372036854775808","/","-1"),Err(RuntimeErrorKind::Overflow("/")));
+}
+#[test]
+fn division_by_zero_is_an_error() {
+    assert_eq!(apply("1","/","0"),Err(RuntimeErrorKind::DivisionByZero));
+    assert_eq!(apply("1","//","0"),Err(RuntimeErrorKind::DivisionByZero));
+    assert_eq!(apply("1.5","/","0"),Err(RuntimeErrorKind::DivisionByZero));
+    assert_eq!(apply("1","/","0.0"),Err(RuntimeErrorKind::DivisionByZero));
+}
+#[test]
+fn ints_become_numbers_with_numbers() {
+    assert_eq!(apply("7","/","2.0"),Ok("Number(3.5)".to_string()));
+    assert_eq!(apply("1.5","+","1"),Ok("Number(2.5)".to_string()));
+    assert_eq!(apply("1","-","0.5"),Ok("Number(0.5)".to_string()));
+}
+#[test]
+fn only_addition_joins_other_types() {
+    assert_eq!(apply("\"a\"","+","\"b\""),Ok("Str(\"ab\")".to_string()));
+    assert_eq!(apply("[1]","+","[2]"),Ok("List([Int(1), Int(2)])".to_string()));
+    assert_eq!(apply("\"a\"","-","\"b\""),Err(RuntimeErrorKind::TypeMismatch{operation:"-",left:"string",right:"string"}));
+    assert_eq!(apply("\"a\"","+","1"),Err(RuntimeErrorKind::TypeMismatch{operation:"+",left:"string",right:"int"}));
+}
+#[test]
+fn ints_and_numbers_compare_exactly() {
+    // 2^53+1 has no number of its own, converting it would round it to 2^53.
+    assert_eq!(apply("9007199254740993","==","9007199254740992.0"),Ok("Bool(false)".to_string()));
+    assert_eq!(apply("9007199254740992.0","<","9007199254740993"),Ok("Bool(true)".to_string()));
+    assert_eq!(apply("3","==","3.0"),Ok("Bool(true)".to_string()));
+    assert_eq!(apply("3",">","2.5"),Ok("Bool(true)".to_string()));
+    assert_eq!(apply("-3","<","-2.5"),Ok("Bool(true)".to_string()));
+}
@@ -0,0 +1,83 @@
+//! Checks that parsing carries on after errors and reports all of them, including calls that go
+//! nowhere.
+use happy::{
+    parse,
+    ErrorKind,
+};
+
+
+#[test]
+fn every_error_is_reported() {
+    let source="\
+0:
+    A:
+        a=,
+        b+,
+        c,
+    ;
+    B(x,x):
+        (x!)?{
+            y=[1,2 3],
+        },
+    ;
+;
+0>A
+";
+    let errors=parse(source,"test.happy").unwrap_err();
+    let found:Vec<_>=errors.iter().map(|e|(&e.kind,e.span.line)).collect();
+    assert_eq!(found,[
+        (&ErrorKind::ExpectedNumber,3),
+        (&ErrorKind::ExpectedVariableName,4),
+        (&ErrorKind::ParameterExists("x".to_string()),7),
+        (&ErrorKind::ExpectedListEnd,9),
+    ]);
+}
+#[test]
+fn errors_in_separate_classes() {
+    let source="0:\n    A:\n        a=b=,\n    ;\n;\n1:\n    A(:\n    ;\n;\n2:\n    A:\n        a,\n    ;\n;\n2>A\n";
+    let errors=parse(source,"test.happy").unwrap_err();
+    let lines:Vec<_>=errors.iter().map(|e|e.span.line).collect();
+    assert_eq!(lines,[3,7]);
+}
+#[test]
+fn errors_point_at_where_they_were_found() {
+    // Parsing `b=5` tries a variable name first, which fails on purpose between the two errors.
+    let source="0:\n    A:\n        a=,\n        b=5,\n        c+,\n    ;\n;\n0>A\n";
+    let errors=parse(source,"test.happy").unwrap_err();
+    let found:Vec<_>=errors.iter().map(|e|(&e.kind,e.span.line,e.span.column)).collect();
+    assert_eq!(found,[
+        (&ErrorKind::ExpectedNumber,3,11),
+        (&ErrorKind::ExpectedVariableName,5,11),
+    ]);
+}
+#[test]
+fn duplicates_point_at_their_names() {
+    let source="0:\n    A(x,x):\n    ;\n    A:\n    ;\n;\n0>A\n";
+    let errors=parse(source,"test.happy").unwrap_err();
+    let found:Vec<_>=errors.iter().map(|e|(&e.kind,e.span.line,e.span.column)).collect();
+    assert_eq!(found,[
+        (&ErrorKind::ParameterExists("x".to_string()),2,9),
+        (&ErrorKind::FunctionExists("A".to_string()),4,5),
+    ]);
+}
+#[test]
+fn calls_that_go_nowhere_are_reported() {
+    let source="\
+0:
+    A:
+        (a!)?{
+            0>C,
+        }:{
+            x=1>A,
+        },
+    ;
+    B:
+    ;
+;
+0>A
+0>D
+";
+    let errors=parse(source,"test.happy").unwrap().link();
+    let found:Vec<_>=errors.iter().map(|e|(e.kind.code(),e.span.line)).collect();
+    assert_eq!(found,[("H0102",4),("H0101",6),("H0102",13)]);
+}
@@ -0,0 +1,46 @@
+//! Checks the numbers a [`Profiler`] collects: calls, operations with and without what a function
+//! called, and how often every operation ran.
+use happy::{
+    parse,
+    Interpreter,
+    Profiler,
+};
+
+
+#[test]
+fn counts_calls_and_operations() {
+    let source="\
+0:
+    A:
+        n=3,
+        0>B(n),
+        0>B(n),
+    ;
+    B(n):
+        one=1,
+        c=n,
+        (c>one)?{
+            n-one,
+            0>B(n),
+        },
+    ;
+;
+0>A
+";
+    let program=parse(source,"test.happy").unwrap();
+    let profiler=Profiler::new();
+    program.run_with(&mut Interpreter::with_hook(profiler.clone())).unwrap();
+    let profile=profiler.profile();
+    let a=&profile.functions[&(0,"A")];
+    assert_eq!((a.calls,a.exclusive_operations,a.inclusive_operations),(1,3,35));
+    assert!(a.inclusive_time>=a.exclusive_time);
+    // `0>B(3)` runs 6 operations, and so does `0>B(2)` it calls, then `0>B(1)` runs 4.
+    let b=&profile.functions[&(0,"B")];
+    assert_eq!((b.calls,b.exclusive_operations),(6,32));
+    // Recursive calls only count towards the outermost one.
+    assert_eq!(b.inclusive_operations,32);
+    let count=|line:usize|profile.operations.values().find(|operation|operation.span.line==line).unwrap().count;
+    assert_eq!(count(8),6);
+    assert_eq!(count(11),4);
+    assert_eq!(profile.operations.values().map(|operation|operation.count).sum::<usize>(),35);
+}
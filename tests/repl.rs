@@ -0,0 +1,48 @@
+//! Checks that REPL entries share a scope, that class entries replace classes and that errors
+//! leave the session usable.
+use happy::{
+    Repl,
+    ReplError,
+    RuntimeErrorKind,
+};
+
+
+/// The values `input` printed, as the REPL prints them.
+fn eval(repl:&Repl,input:&str)->Vec<String> {
+    return repl.eval(input).unwrap().iter().map(|value|value.quoted()).collect();
+}
+
+
+#[test]
+fn scope_lives_between_entries() {
+    let repl=Repl::new();
+    assert_eq!(eval(&repl,"a=2\n"),["2"]);
+    assert_eq!(eval(&repl,"b=3,\nc=\"c\",\n"),["3","\"c\""]);
+    assert_eq!(eval(&repl,"a*b\n"),["6"]);
+}
+#[test]
+fn classes_are_replaced() {
+    let repl=Repl::new();
+    assert!(eval(&repl,"0:\n    A:\n        ^1,\n    ;\n;\n").is_empty());
+    assert_eq!(eval(&repl,"r=0>A\n"),["1"]);
+    assert_eq!(eval(&repl,"0:\n    A:\n        ^2,\n    ;\n;\n0>A\n"),["2"]);
+    assert_eq!(eval(&repl,"r=0>A\n"),["2"]);
+}
+#[test]
+fn errors_keep_the_session() {
+    let repl=Repl::new();
+    assert!(matches!(repl.eval("a=\n"),Err(ReplError::Parse(_))));
+    match repl.eval("x=1,\ny=0,\nx/y,\n") {
+        Err(ReplError::Runtime(error))=>assert_eq!(error.kind,RuntimeErrorKind::DivisionByZero),
+        result=>panic!("expected a runtime error, found {:?}",result),
+    }
+    assert_eq!(eval(&repl,"z=x\n"),["1"]);
+}
+#[test]
+fn entries_continue_until_closed() {
+    assert!(!Repl::is_complete("0:\n    A:\n"));
+    assert!(!Repl::is_complete("s=\"a;\n"));
+    assert!(!Repl::is_complete("l=[1,\n"));
+    assert!(Repl::is_complete("0:\n    A:\n    ;\n;\n"));
+    assert!(Repl::is_complete("(c<n)?{\n    c,\n}:{\n    n,\n},\n"));
+}
@@ -0,0 +1,73 @@
+//! Checks that every node of a parsed program knows where it starts in the source.
+use happy::{
+    parse,
+    OperationKind,
+    Span,
+};
+
+
+/// The line and column of `span`.
+fn at(span:Span)->(usize,usize) {
+    (span.line,span.column)
+}
+
+
+#[test]
+fn every_node_has_a_span() {
+    let source="\
+0:
+    A(x):
+        a=[x,1],
+        r=0>B(a, \"b\"),
+        ^r,
+    ;
+    B(l,s):
+        (l==s)?{
+            ^,
+        },
+    ;
+;
+0>A(2)
+";
+    let program=parse(source,"test.happy").unwrap();
+    let class=&program.classes[&0];
+    assert_eq!(at(class.span),(1,1));
+    let a=&class.functions["A"];
+    assert_eq!(at(a.span),(2,5));
+    let operations:Vec<_>=a.operations.iter().map(|operation|at(operation.span)).collect();
+    assert_eq!(operations,[(3,9),(4,9),(5,9)]);
+    match &a.operations[0].kind {
+        OperationKind::Assign(_,value)=>assert_eq!(at(value.span),(3,11)),
+        kind=>panic!("expected an assignment, found {:?}",kind),
+    }
+    match &a.operations[1].kind {
+        OperationKind::AssignCall(_,call)=>{
+            assert_eq!(at(call.span),(4,11));
+            let arguments:Vec<_>=call.arguments.iter().map(|argument|at(argument.span)).collect();
+            assert_eq!(arguments,[(4,15),(4,18)]);
+        },
+        kind=>panic!("expected a call, found {:?}",kind),
+    }
+    match &a.operations[2].kind {
+        OperationKind::Return(value)=>assert_eq!(at(value.span),(5,10)),
+        kind=>panic!("expected a return, found {:?}",kind),
+    }
+    let b=&class.functions["B"];
+    assert_eq!(at(b.span),(7,5));
+    assert_eq!(at(b.operations[0].span),(8,9));
+    match &b.operations[0].kind {
+        OperationKind::Conditional{to_compare,inner,..}=>{
+            assert_eq!(at(to_compare.span),(8,10));
+            assert_eq!(at(inner[0].span),(9,13));
+            match &inner[0].kind {
+                // A bare `^` returns nothing, which is where its value would be.
+                OperationKind::Return(value)=>assert_eq!(at(value.span),(9,14)),
+                kind=>panic!("expected a return, found {:?}",kind),
+            }
+        },
+        kind=>panic!("expected a conditional, found {:?}",kind),
+    }
+    let statement=&program.statements[0];
+    assert_eq!(at(statement.span),(13,1));
+    assert_eq!(at(statement.arguments[0].span),(13,5));
+}
@@ -0,0 +1,65 @@
+//! Checks that programs read from and write to the streams they are given instead of the real
+//! ones, with the interpreter and with the VM.
+use std::io::Write;
+use happy::{
+    parse,
+    Hook,
+    Interpreter,
+    Vm,
+};
+
+
+const SOURCE:&str="\
+0:
+    A:
+        a$,
+        a,
+        0>B,
+    ;
+    B:
+        b$#,
+        one=1,
+        b+one,
+        b,
+    ;
+;
+0>A
+";
+
+
+/// A hook that writes every function it enters to the interpreter's stderr.
+struct Calls;
+impl<'doc> Hook<'doc> for Calls {
+    fn enter(&mut self,interpreter:&mut Interpreter<'doc>) {
+        let function=interpreter.stack().last().unwrap().to_string();
+        writeln!(interpreter.stderr(),"{}",function).unwrap();
+    }
+}
+
+
+#[test]
+fn interpreter_uses_the_given_streams() {
+    let program=parse(SOURCE,"test.happy").unwrap();
+    let mut output=Vec::new();
+    let mut errors=Vec::new();
+    let mut interpreter=Interpreter::with_hook(Calls);
+    interpreter.set_stdin("hi\n41\n".as_bytes());
+    interpreter.set_stdout(&mut output);
+    interpreter.set_stderr(&mut errors);
+    program.run_with(&mut interpreter).unwrap();
+    drop(interpreter);
+    assert_eq!(String::from_utf8(output).unwrap(),"hi42");
+    assert_eq!(String::from_utf8(errors).unwrap(),"0>A\n0>B\n");
+}
+#[test]
+fn vm_uses_the_given_streams() {
+    let program=parse(SOURCE,"test.happy").unwrap();
+    let bytecode=program.compile();
+    let mut output=Vec::new();
+    let mut vm=Vm::new();
+    vm.set_stdin("hi\n41\n".as_bytes());
+    vm.set_stdout(&mut output);
+    vm.run(&bytecode).unwrap();
+    drop(vm);
+    assert_eq!(String::from_utf8(output).unwrap(),"hi42");
+}
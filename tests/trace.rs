@@ -0,0 +1,40 @@
+//! Checks the lines `--trace=json` writes: one per operation once it ran, innermost calls first.
+use happy::{
+    parse,
+    Interpreter,
+    TraceFormat,
+    Tracer,
+};
+
+
+#[test]
+fn json_lines() {
+    let source="\
+0:
+    A:
+        a=1,
+        b=2,
+        a+b,
+        0>B(a),
+    ;
+    B(x):
+        ^x,
+    ;
+;
+0>A
+";
+    let program=parse(source,"test.happy").unwrap();
+    let mut output=Vec::new();
+    let mut interpreter=Interpreter::with_hook(Tracer::new(TraceFormat::Json,&mut output));
+    program.run_with(&mut interpreter).unwrap();
+    drop(interpreter);
+    let output=String::from_utf8(output).unwrap();
+    let lines:Vec<_>=output.lines().collect();
+    assert_eq!(lines,[
+        r#"{"op":"Assign","file":"test.happy","line":3,"column":9,"depth":1,"function":"0>A","variables":[{"name":"a","before":null,"after":1}]}"#,
+        r#"{"op":"Assign","file":"test.happy","line":4,"column":9,"depth":1,"function":"0>A","variables":[{"name":"b","before":null,"after":2}]}"#,
+        r#"{"op":"Add","file":"test.happy","line":5,"column":9,"depth":1,"function":"0>A","variables":[{"name":"a","before":1,"after":3},{"name":"b","before":2,"after":2}]}"#,
+        r#"{"op":"Return","file":"test.happy","line":9,"column":9,"depth":2,"function":"0>B","variables":[{"name":"x","before":3,"after":3}]}"#,
+        r#"{"op":"Call","file":"test.happy","line":6,"column":9,"depth":1,"function":"0>A","variables":[{"name":"a","before":3,"after":3}]}"#,
+    ]);
+}
@@ -0,0 +1,168 @@
+//! Checks that the bytecode VM prints the same and stops with the same errors as the interpreter,
+//! including when a program runs into one of its [`Limits`].
+use std::mem::size_of;
+use happy::{
+    parse,
+    Data,
+    Interpreter,
+    Limits,
+    RuntimeErrorKind,
+    Vm,
+};
+
+
+/// What `source` printed and the error it stopped with as JSON, when run with `limits` by the VM
+/// or by the interpreter.
+fn run(source:&str,limits:Limits,vm:bool)->(String,Option<String>) {
+    let program=parse(source,"test.happy").unwrap();
+    let mut output=Vec::new();
+    let result=if vm {
+        let bytecode=program.compile();
+        let mut vm=Vm::new();
+        vm.set_limits(limits);
+        vm.set_stdout(&mut output);
+        vm.run(&bytecode).map_err(|error|error.to_json(source))
+    } else {
+        let mut interpreter=Interpreter::new();
+        interpreter.set_limits(limits);
+        interpreter.set_stdout(&mut output);
+        program.run_with(&mut interpreter).map_err(|error|error.to_json(source))
+    };
+    return (String::from_utf8(output).unwrap(),result.err());
+}
+/// Run `source` with both engines, check that they agree and return what they did.
+fn both(source:&str,limits:Limits)->(String,Option<String>) {
+    let interpreted=run(source,limits,false);
+    assert_eq!(run(source,limits,true),interpreted);
+    return interpreted;
+}
+
+
+#[test]
+fn same_output() {
+    let source="\
+0:
+    A:
+        nl=\"\\n\",
+        r=0>F(10),
+        r,
+        nl,
+        l=[1,\"two\",{3:4.5}],
+        l<<r,
+        l,
+        nl,
+        s=#l,
+        s,
+    ;
+    F(n):
+        one=1,
+        c=n,
+        (c<=one)?{
+            ^one,
+        },
+        m=n,
+        m-one,
+        r=0>F(m),
+        r*n,
+        ^r,
+    ;
+;
+0>A
+";
+    assert_eq!(both(source,Limits::default()),("3628800\n[1, \"two\", {3: 4.5}, 3628800]\n4".to_string(),None));
+}
+#[test]
+fn same_errors() {
+    let source="0:\n    A:\n        a=1,\n        b=\"b\",\n        a,\n        0>B(a,b),\n    ;\n    B(x,y):\n        x+y,\n    ;\n;\n0>A\n";
+    let (output,error)=both(source,Limits::default());
+    assert_eq!(output,"1");
+    assert!(error.unwrap().contains(r#""code":"H0104""#));
+}
+#[test]
+fn operation_limit() {
+    let source="\
+0:
+    A:
+        t=true,
+        c=t,
+        i=0,
+        one=1,
+        (c==t)@{
+            i+one,
+            i,
+        },
+    ;
+;
+0>A
+";
+    let limits=Limits{operations:Some(50),..Limits::default()};
+    let (output,error)=both(source,limits);
+    // 5 operations before the loop, then 3 for every round.
+    assert_eq!(output,"123456789101112131415");
+    assert!(error.unwrap().contains(r#""code":"H0116""#));
+}
+#[test]
+fn call_depth_limit() {
+    let source="\
+0:
+    A(n):
+        one=1,
+        n+one,
+        n,
+        0>A(n),
+    ;
+;
+0>A(0)
+";
+    let limits=Limits{call_depth:Some(5),..Limits::default()};
+    let (output,error)=both(source,limits);
+    assert_eq!(output,"12345");
+    assert!(error.unwrap().contains(r#""code":"H0117""#));
+}
+#[test]
+fn memory_limit() {
+    let source="\
+0:
+    A:
+        l=[],
+        s=\"abcdefgh\",
+        t=true,
+        c=t,
+        (c==t)@{
+            l<<s,
+            n=#l,
+            n,
+        },
+    ;
+;
+0>A
+";
+    let limits=Limits{memory:Some(1000),..Limits::default()};
+    let (output,error)=both(source,limits);
+    // `s` itself, then every push adds a copy of it as an item of the list.
+    let item=size_of::<Data>()+"abcdefgh".len();
+    let rounds=(1000-"abcdefgh".len())/item;
+    assert_eq!(output,(1..=rounds).map(|n|n.to_string()).collect::<String>());
+    assert!(error.unwrap().contains(r#""code":"H0118""#));
+}
+#[test]
+fn failed_changes_use_no_memory() {
+    // The interpreter is kept between runs like the REPL keeps it, so memory counted for a push
+    // or remove that failed would add up until the limit is reached.
+    let source="0:\n    A:\n        m={},\n        s=\"abcdefgh\",\n        m<<s,\n    ;\n;\n0>A\n";
+    let program=parse(source,"test.happy").unwrap();
+    let mut interpreter=Interpreter::new();
+    interpreter.set_limits(Limits{memory:Some(100),..Limits::default()});
+    for _ in 0..20 {
+        let error=program.run_with(&mut interpreter).unwrap_err();
+        assert_eq!(error.kind,RuntimeErrorKind::InvalidOperand{operation:"<<",found:"map"});
+    }
+}
+#[test]
+fn endless_recursion_stops_by_default() {
+    let source="0:\n    A:\n        0>A,\n    ;\n;\n0>A\n";
+    let program=parse(source,"test.happy").unwrap();
+    let error=program.compile().run().unwrap_err();
+    assert_eq!(error.kind,RuntimeErrorKind::CallDepthLimit(Limits::default().call_depth.unwrap()));
+    assert_eq!(error.stack.len(),Limits::default().call_depth.unwrap());
+}